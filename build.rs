@@ -1,4 +1,14 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
 fn main() {
+    // Own memory.x instead of the one from embassy-stm32, it leaves out the settings page
+    let out = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    fs::copy("memory.x", out.join("memory.x")).unwrap();
+    println!("cargo:rustc-link-search={}", out.display());
+    println!("cargo:rerun-if-changed=memory.x");
+
     println!("cargo:rustc-link-arg-bins=--nmagic");
     println!("cargo:rustc-link-arg-bins=-Tlink.x");
     println!("cargo:rustc-link-arg-bins=-Tdefmt.x");
@@ -0,0 +1,6 @@
+MEMORY
+{
+  /* STM32F103C8, the last 1 KiB page of the flash holds the settings (src/settings.rs) */
+  FLASH : ORIGIN = 0x08000000, LENGTH = 63K
+  RAM : ORIGIN = 0x20000000, LENGTH = 20K
+}
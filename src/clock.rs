@@ -1,107 +1,240 @@
+use core::fmt::Write;
 use core::ops::ControlFlow;
 use defmt::*;
-use embassy_stm32::dma::NoDma;
-use embassy_stm32::peripherals::{I2C1, PA5, PA7, PB0};
-use ds1307::{DateTimeAccess, Datelike, Ds1307, NaiveDateTime, Timelike};
-use max7219::connectors::PinConnector;
-use max7219::{DataError, MAX7219};
-use embassy_stm32::gpio::Output;
-use embassy_stm32::i2c::I2c;
-use crate::utils::{self, alarm::Alarm, buttons::Buttons};
+use ds1307::{Datelike, NaiveDateTime, Timelike};
+use crate::utils::{self, alarm::Alarm, buttons::{Buttons, HoldTimer}, rtc::Rtc, Display};
 use crate::utils::{matrix_display, symbols};
 use crate::utils::Mode;
 use crate::utils::matrix_display::MatrixDisplay;
+use crate::error::ClockError;
+use crate::utils::brightness;
+use crate::utils::night::Night;
+use crate::settings::{self, DateFormat, Face, HourFormat};
+use crate::utils::animation::Slide;
+use crate::utils::rotation::Rotation;
+use crate::utils::thermometer;
+use embassy_time::{Duration, Instant};
+use heapless::String;
+use crate::utils::watchdog::{self, Activity};
+use crate::faces::{self, ClockFace, FaceContext};
+use crate::locale;
+
+const ALARM_WAKE_TIME: Duration = Duration::from_secs(60);
 
 pub enum ClockMode {
     Time,
     Date,
     Year,
+    Weekday,
+    Temperature,
+}
+
+impl From<Face> for ClockMode {
+    fn from(face: Face) -> Self {
+        match face {
+            Face::Time => ClockMode::Time,
+            Face::Date => ClockMode::Date,
+            Face::Year => ClockMode::Year,
+            Face::Weekday => ClockMode::Weekday,
+            Face::Temperature => ClockMode::Temperature,
+        }
+    }
 }
 
 impl Mode for ClockMode {
     fn next(&self) -> Self {
         match self {
             ClockMode::Time => ClockMode::Date,
-            ClockMode::Date => ClockMode::Time,
-            ClockMode::Year => ClockMode::Date,
+            ClockMode::Date => ClockMode::Year,
+            ClockMode::Year => ClockMode::Weekday,
+            ClockMode::Weekday => ClockMode::Temperature,
+            ClockMode::Temperature => ClockMode::Time,
         }
     }
 
     fn prev(&self) -> Self {
         match self {
-            ClockMode::Time => ClockMode::Date,
+            ClockMode::Time => ClockMode::Temperature,
             ClockMode::Date => ClockMode::Time,
             ClockMode::Year => ClockMode::Date,
+            ClockMode::Weekday => ClockMode::Year,
+            ClockMode::Temperature => ClockMode::Weekday,
         }
     }
 }
 
-
 pub async fn clock_mode<'a>(
-    rtc: &mut Ds1307<I2c<'a, I2C1, NoDma, NoDma>>, 
-    display: &mut MAX7219<PinConnector<Output<'a, PA7>, Output<'a, PB0>, Output<'a, PA5>>>,
+    rtc: &mut Rtc<'a>, 
+    display: &mut Display<'a>,
     buttons: &utils::buttons::Buttons<'a>,
     alarm: &mut utils::alarm::Alarm<'a>
 ) {
     let mut matrices = MatrixDisplay::new();
-    let mut mode: ClockMode = ClockMode::Time;
+    let mut rotation = Rotation::new();
+    // First face matches the slot whose dwell time the rotation starts counting
+    let mut mode: ClockMode = rotation.face().map_or(ClockMode::Time, ClockMode::from);
+    // Scrolling faces start from the beginning whenever they are switched to
+    let mut mode_shown = Instant::now();
+    let mut slide: Option<Slide> = None;
 
     let mut changed = false;
-    let mut is_late = false;
     let mut last_second = 0;
 
-    let mut hold_time_main = 0;
-    let mut hold_time_exit = 0;
+    let mut hold_main = HoldTimer::default();
+    let mut hold_exit = HoldTimer::default();
+
+    let mut night = Night::new();
 
     info!("Clock");
+    watchdog::resume(Activity::RtcTick);
     loop {
-        if let ControlFlow::Break(_) = buttons.button_hold(&mut hold_time_main, true).await { break; }
-        if let ControlFlow::Break(_) = buttons.button_hold(&mut hold_time_exit, false).await { break; }
+        if night.is_dark() {
+            // Press only wakes the display, it does not reach the clock
+            if buttons.any_pin_is_low().await {
+                night.wake(Duration::from_secs(settings::get().night.wake_seconds as u64));
+                buttons.wait_release().await;
+            }
+        } else {
+            if let ControlFlow::Break(_) = buttons.button_hold(&mut hold_main, true).await { break; }
+            if let ControlFlow::Break(_) = buttons.button_hold(&mut hold_exit, false).await { break; }
 
-        buttons.mode_change(&mut mode, true).await;
+            if buttons.mode_change(&mut mode, true).await {
+                rotation.pause();
+                mode_shown = Instant::now();
+                slide = Some(Slide::new(matrices.frame()));
+            }
+        }
 
-        if let Ok(datetime) = rtc_read(rtc, &mut last_second, &mut changed) {
-            if changed {
-                calc_digits(&mode, &datetime, &mut matrices);
-                prepare_display(&mut matrices, &mode, last_second%2==0);
-                if let Err(_) = check_intensity(datetime.hour(), &mut is_late, display) {
-                    matrices.set_error();
+        if let Some(face) = rotation.next_face() {
+            mode = face.into();
+            mode_shown = Instant::now();
+            slide = Some(Slide::new(matrices.frame()));
+        }
+
+        let read = rtc_read(rtc, &mut last_second, &mut changed);
+        watchdog::report(Activity::RtcTick);
+
+        match read {
+            Ok(datetime) => if changed {
+                night.update(&datetime);
+                let context = FaceContext { shown: mode_shown, is_even: last_second % 2 == 0 };
+                faces::face_for(&mode).render(&datetime, &context, &mut matrices);
+                if slide.as_ref().is_some_and(|slide| !slide.render(&mut matrices)) {
+                    slide = None;
+                }
+                if rtc.is_degraded() {
+                    matrices.set_degraded();
+                }
+                if !rtc.is_time_set() && last_second % 2 == 1 {
+                    matrices.set_time_warning();
+                }
+                brightness::follow_schedule(&datetime);
+
+                if let Err(err) = check_alarm(alarm, last_second, datetime, buttons, display, &mut night).await {
+                    matrices.set_error(err);
                 }
+            }
+            Err(err) => matrices.set_error(err),
+        }
+        rtc.recover_bus().await;
 
-                check_alarm(alarm, last_second, datetime, buttons, display).await;
-        }} else {
-            matrices.set_error();
+        if let Err(err) = night.apply(&mut matrices, display).await {
+            matrices.set_error(err);
         }
-        
-       matrices.display_update(display);
+       matrices.display_update(display).await;
 
     }
+    if let Err(err) = night.leave(display).await {
+        error!{"{}", err};
+    }
+    watchdog::pause(Activity::RtcTick);
 }
 
-async fn check_alarm<'a>(alarm: &mut Alarm<'_>, last_second: u32, datetime: NaiveDateTime, buttons: &Buttons<'a>,  display: &mut MAX7219<PinConnector<Output<'a, PA7>, Output<'a, PB0>, Output<'a, PA5>>>) {
+async fn check_alarm<'a>(alarm: &mut Alarm<'_>, last_second: u32, datetime: NaiveDateTime, buttons: &Buttons<'a>,  display: &mut Display<'a>, night: &mut Night) -> Result<(), ClockError> {
     if alarm.is_enable() && last_second == 1 {
         if alarm.get_hour() == datetime.hour() && alarm.get_minute() == datetime.minute() {
-            alarm.play_alarm(buttons, display).await;
+            // Alarm always wakes a dark display and leaves the time up for a while
+            night.wake(ALARM_WAKE_TIME);
+            night.leave(display).await?;
+
+            // Alarm keeps the main task away from the RTC until it is silenced
+            watchdog::pause(Activity::RtcTick);
+            let played = alarm.play_alarm(buttons, display).await;
+            watchdog::resume(Activity::RtcTick);
+            played?;
         }
     }
+    Ok(())
 }
 
-fn check_intensity(hour: u32, is_late: &mut bool, display: &mut MAX7219<PinConnector<Output<'_, PA7>, Output<'_, PB0>, Output<'_, PA5>>>) -> Result<(), DataError>{        
-    if *is_late {
-        if hour < 23 as u32 && hour >= 6 as u32 {
-            info!{"Hello at morning"}
-            utils::set_display_intensity(display, 3)?;
-            *is_late = false;
-        }
-    } else {
-        if hour >= 23 as u32 || hour < 6 as u32 {
-            info!{"Hello at night"}
-
-            utils::set_display_intensity(display, 0)?;
-            *is_late = true;
+/// Digit faces, dates in the format chosen in the settings.
+impl ClockFace for ClockMode {
+    fn render(&self, datetime: &NaiveDateTime, context: &FaceContext, matrices: &mut MatrixDisplay) {
+        let format = settings::get().date_format;
+        let pack = locale::pack();
+        let weekday = pack.weekdays[datetime.weekday().num_days_from_monday() as usize];
+        match (self, format) {
+            (ClockMode::Date, DateFormat::MonthDay) => {
+                calc_digits(self, datetime, matrices);
+                core::mem::swap(&mut matrices.first_matrix, &mut matrices.third_matrix);
+                core::mem::swap(&mut matrices.second_matrix, &mut matrices.fourth_matrix);
+                shift_digits(matrices);
+                add_slash(&mut matrices.second_matrix, &mut matrices.third_matrix);
+            }
+            (ClockMode::Weekday, _) => {
+                let mut text: String<8> = String::new();
+                let _ = core::write!(text, " {}", weekday);
+                matrices.set_text(&text);
+            }
+            (ClockMode::Temperature, _) => match thermometer::temperature() {
+                Ok(tenths) => {
+                    let degrees = (tenths + if tenths < 0 { -5 } else { 5 }) / 10;
+                    let mut text: String<8> = String::new();
+                    let _ = if degrees > -10 {
+                        core::write!(text, "{:>2}°C", degrees)
+                    } else {
+                        core::write!(text, "{}°", degrees)
+                    };
+                    matrices.set_text(&text);
+                }
+                // Only the code, the sensor task already logged the failure
+                Err(err) => matrices.set_text(err.code()),
+            },
+            (ClockMode::Date, DateFormat::Iso | DateFormat::Long) => {
+                let mut cycle: String<32> = String::new();
+                let _ = match format {
+                    DateFormat::Iso => core::write!(cycle, "{}-{:02}-{:02}    ", datetime.year(), datetime.month(), datetime.day()),
+                    _ => core::write!(cycle, "{} {} {} {}    ",
+                        weekday,
+                        datetime.day(),
+                        pack.months[datetime.month0() as usize],
+                        datetime.year()),
+                };
+                matrices.set_looped_text(&cycle, context.scroll_offset());
+            }
+            _ => {
+                calc_digits(self, datetime, matrices);
+                prepare_display(matrices, self, context.is_even);
+            }
         }
     }
-    Ok(())
+}
+
+// Slash between the month and the day
+fn add_slash(matrix_one: &mut [u8; 8], matrix_two: &mut [u8; 8]) {
+    matrix_one[4] |= 1;
+    matrix_one[5] |= 1;
+    matrix_one[6] |= 1;
+
+    matrix_two[1] |= 128;
+    matrix_two[2] |= 128;
+    matrix_two[3] |= 128;
+}
+
+fn shift_digits(matrices: &mut MatrixDisplay) {
+    utils::shift_bits(&mut matrices.first_matrix, 1);
+    utils::shift_bits(&mut matrices.third_matrix, 2);
+    utils::shift_bits(&mut matrices.fourth_matrix, 1);
 }
 
 pub fn prepare_display(
@@ -109,18 +242,16 @@ pub fn prepare_display(
     mode: &ClockMode, 
     is_even: bool,  
 ) {
-    utils::shift_bits(&mut matrices.first_matrix, 1);
-    utils::shift_bits(&mut matrices.third_matrix, 2);
-    utils::shift_bits(&mut matrices.fourth_matrix, 1);
+    shift_digits(matrices);
 
     add_dots(mode, is_even, &mut matrices.second_matrix, &mut matrices.third_matrix);
 }
 
 pub fn rtc_read(
-    rtc: &mut Ds1307<I2c<'_, I2C1, NoDma, NoDma>>, 
+    rtc: &mut Rtc<'_>, 
     last_second: &mut u32, 
     changed: &mut bool
-) -> Result<NaiveDateTime, ()> {
+) -> Result<NaiveDateTime, ClockError> {
     match rtc.datetime() {
         Ok(datetime) => {
             if datetime.second() != *last_second {
@@ -128,14 +259,15 @@ pub fn rtc_read(
                 *changed = true;
                 
                 if let Some(hour) = time_change(&datetime){
-                    rtc.set_datetime(&datetime.with_hour(hour).unwrap()).unwrap();
+                    let changed_time = datetime.with_hour(hour).ok_or(ClockError::InvalidDate)?;
+                    rtc.set_datetime(&changed_time)?;
                 }
             } 
             Ok(datetime)
         }
-        Err(_) => {
+        Err(err) => {
             info!("RTC read failed!");
-            Err(())
+            Err(err.into())
         }
     }
 }
@@ -163,10 +295,13 @@ pub fn calc_digits(
     datetime: &NaiveDateTime, 
     matrices: &mut matrix_display::MatrixDisplay
 ) {
+    let twelve_hour = matches!(mode, ClockMode::Time) && settings::get().hour_format == HourFormat::H12;
+    let hour = if twelve_hour { (datetime.hour() + 11) % 12 + 1 } else { datetime.hour() };
+
     let (first_digit, second_digit, third_digit, fourth_digit) = match mode {
         ClockMode::Time => (
-            (datetime.hour() / 10) as usize,
-            (datetime.hour() % 10) as usize,
+            (hour / 10) as usize,
+            (hour % 10) as usize,
             (datetime.minute() / 10) as usize,
             (datetime.minute() % 10) as usize,
         ),
@@ -183,12 +318,24 @@ pub fn calc_digits(
             (datetime.year() / 10 % 10) as usize,
             (datetime.year() % 10) as usize,
         ),
+        // Drawn as text by the `ClockFace` impl
+        ClockMode::Weekday | ClockMode::Temperature => return,
     };
 
     matrices.first_matrix = symbols::DIGITS[first_digit];
     matrices.second_matrix = symbols::DIGITS[second_digit];
     matrices.third_matrix = symbols::DIGITS[third_digit];
     matrices.fourth_matrix = symbols::DIGITS[fourth_digit];
+
+    if twelve_hour {
+        if hour < 10 {
+            matrices.first_matrix = symbols::BLANK;
+        }
+        // PM marker in the empty bottom row under the hour
+        if datetime.hour() >= 12 {
+            matrices.first_matrix[7] |= 0xC0;
+        }
+    }
 }
 
 pub fn add_dots(mode: &ClockMode, is_even: bool, matrix_one: &mut [u8; 8], matrix_two: &mut [u8; 8]) {
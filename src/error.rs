@@ -0,0 +1,66 @@
+use ds1307::Error;
+use embassy_stm32::{flash, i2c, spi};
+
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub enum DisplayFault {
+    Spi(spi::Error),
+    I2c(i2c::Error),
+}
+
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub enum ClockError {
+    /// I2C transfer to the DS1307 failed
+    Rtc(i2c::Error),
+    /// Display backend could not be written
+    Display(DisplayFault),
+    /// DS18B20 did not answer or returned garbage
+    Sensor,
+    /// Stored settings failed the CRC check
+    SettingsCrc,
+    /// Settings page could not be erased or written
+    Flash(flash::Error),
+    /// Date or time out of range for the RTC
+    InvalidDate,
+}
+
+impl ClockError {
+    /// Four characters shown on the display, one per matrix.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ClockError::Rtc(_) => "E:RT",
+            ClockError::Display(_) => "E:DS",
+            ClockError::Sensor => "E:SN",
+            ClockError::SettingsCrc => "E:CR",
+            ClockError::Flash(_) => "E:FL",
+            ClockError::InvalidDate => "E:DT",
+        }
+    }
+}
+
+impl From<Error<i2c::Error>> for ClockError {
+    fn from(err: Error<i2c::Error>) -> Self {
+        match err {
+            Error::I2C(err) => ClockError::Rtc(err),
+            Error::InvalidInputData => ClockError::InvalidDate,
+        }
+    }
+}
+
+impl From<spi::Error> for ClockError {
+    fn from(err: spi::Error) -> Self {
+        ClockError::Display(DisplayFault::Spi(err))
+    }
+}
+
+// Plain I2C errors come from the I2C display backends, DS1307 errors are wrapped by its driver
+impl From<i2c::Error> for ClockError {
+    fn from(err: i2c::Error) -> Self {
+        ClockError::Display(DisplayFault::I2c(err))
+    }
+}
+
+impl From<flash::Error> for ClockError {
+    fn from(err: flash::Error) -> Self {
+        ClockError::Flash(err)
+    }
+}
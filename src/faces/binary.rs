@@ -0,0 +1,34 @@
+use ds1307::{NaiveDateTime, Timelike};
+
+use crate::settings::{self, HourFormat};
+use crate::utils::matrix_display::MatrixDisplay;
+use crate::utils::symbols::BLANK;
+use super::{ClockFace, FaceContext};
+
+// Left column of each digit, hours, minutes and seconds pairs set apart
+const DIGIT_COLUMNS: [usize; 6] = [2, 6, 13, 17, 24, 28];
+
+/// BCD clock, a column of 2x2 squares per digit of hours, minutes and seconds
+/// with the highest bit on top.
+pub struct BinaryFace;
+
+impl ClockFace for BinaryFace {
+    fn render(&self, datetime: &NaiveDateTime, _context: &FaceContext, matrices: &mut MatrixDisplay) {
+        let hour = match settings::get().hour_format {
+            HourFormat::H24 => datetime.hour(),
+            HourFormat::H12 => (datetime.hour() + 11) % 12 + 1,
+        };
+        let (minute, second) = (datetime.minute(), datetime.second());
+        let digits = [hour / 10, hour % 10, minute / 10, minute % 10, second / 10, second % 10];
+
+        matrices.set_frame([BLANK; 4]);
+        for (digit, column) in digits.into_iter().zip(DIGIT_COLUMNS) {
+            for bit in (0..4).filter(|bit| digit & (1 << bit) != 0) {
+                let row = (3 - bit) * 2;
+                for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    matrices.set_pixel(column + x, row + y);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use ds1307::{NaiveDateTime, Timelike};
+
+use crate::utils::matrix_display::MatrixDisplay;
+use crate::utils::symbols::BLANK;
+use super::{ClockFace, FaceContext};
+
+// Columns inside the frame
+const BAR_COLUMNS: u32 = 30;
+const SECONDS_PER_DAY: u32 = 24 * 60 * 60;
+
+/// Share of the day gone by, a framed bar filling from the left with marks
+/// under 6, 12 and 18 o'clock.
+pub struct DayBarFace;
+
+impl ClockFace for DayBarFace {
+    fn render(&self, datetime: &NaiveDateTime, _context: &FaceContext, matrices: &mut MatrixDisplay) {
+        matrices.set_frame([BLANK; 4]);
+
+        for column in 0..32 {
+            matrices.set_pixel(column, 1);
+            matrices.set_pixel(column, 6);
+        }
+        for row in 1..7 {
+            matrices.set_pixel(0, row);
+            matrices.set_pixel(31, row);
+        }
+
+        let filled = (datetime.num_seconds_from_midnight() * BAR_COLUMNS / SECONDS_PER_DAY) as usize;
+        for column in 1..=filled {
+            for row in 2..6 {
+                matrices.set_pixel(column, row);
+            }
+        }
+
+        for quarter in 1..4 {
+            matrices.set_pixel(1 + (BAR_COLUMNS * quarter / 4) as usize, 7);
+        }
+    }
+}
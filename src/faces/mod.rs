@@ -0,0 +1,45 @@
+use ds1307::NaiveDateTime;
+use embassy_time::{Duration, Instant};
+
+use crate::clock::ClockMode;
+use crate::settings::{self, TimeFace};
+use crate::utils::matrix_display::MatrixDisplay;
+
+mod binary;
+mod day_bar;
+mod roman;
+mod words;
+
+// Scrolling faces move one column per step
+const SCROLL_STEP: Duration = Duration::from_millis(60);
+
+/// State of the clock screen a face may use besides the time.
+pub struct FaceContext {
+    /// When the face was switched to, scrolling starts from there
+    pub shown: Instant,
+    /// Even second, for blinking separators
+    pub is_even: bool,
+}
+
+impl FaceContext {
+    /// Columns a scrolling face has moved since it was shown.
+    pub fn scroll_offset(&self) -> usize {
+        (self.shown.elapsed().as_millis() / SCROLL_STEP.as_millis()) as usize
+    }
+}
+
+/// Draws the clock screen from the local time.
+pub trait ClockFace {
+    fn render(&self, datetime: &NaiveDateTime, context: &FaceContext, matrices: &mut MatrixDisplay);
+}
+
+/// Face for `mode`, the time drawn in the style chosen in the settings.
+pub fn face_for(mode: &ClockMode) -> &dyn ClockFace {
+    match (mode, settings::get().time_face) {
+        (ClockMode::Time, TimeFace::Binary) => &binary::BinaryFace,
+        (ClockMode::Time, TimeFace::DayBar) => &day_bar::DayBarFace,
+        (ClockMode::Time, TimeFace::Roman) => &roman::RomanFace,
+        (ClockMode::Time, TimeFace::Words) => &words::WordsFace,
+        _ => mode,
+    }
+}
@@ -0,0 +1,41 @@
+use ds1307::{NaiveDateTime, Timelike};
+use heapless::String;
+
+use crate::settings::{self, HourFormat};
+use crate::utils::matrix_display::MatrixDisplay;
+use super::{ClockFace, FaceContext};
+
+// Enough for anything below 60
+const NUMERALS: [(u32, &str); 7] = [(50, "L"), (40, "XL"), (10, "X"), (9, "IX"), (5, "V"), (4, "IV"), (1, "I")];
+
+/// Hours and minutes in Roman numerals scrolling by, N (nulla) standing for zero.
+pub struct RomanFace;
+
+impl ClockFace for RomanFace {
+    fn render(&self, datetime: &NaiveDateTime, context: &FaceContext, matrices: &mut MatrixDisplay) {
+        let hour = match settings::get().hour_format {
+            HourFormat::H24 => datetime.hour(),
+            HourFormat::H12 => (datetime.hour() + 11) % 12 + 1,
+        };
+
+        let mut text: String<24> = String::new();
+        push_roman(&mut text, hour);
+        let _ = text.push(':');
+        push_roman(&mut text, datetime.minute());
+        let _ = text.push_str("    ");
+
+        matrices.set_looped_text(&text, context.scroll_offset());
+    }
+}
+
+fn push_roman(text: &mut String<24>, mut value: u32) {
+    if value == 0 {
+        let _ = text.push('N');
+    }
+    for (step, numeral) in NUMERALS {
+        while value >= step {
+            let _ = text.push_str(numeral);
+            value -= step;
+        }
+    }
+}
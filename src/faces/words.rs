@@ -0,0 +1,61 @@
+use ds1307::{NaiveDateTime, Timelike};
+use heapless::String;
+
+use crate::settings::{self, Language};
+use crate::utils::matrix_display::MatrixDisplay;
+use super::{ClockFace, FaceContext};
+
+// Minutes in five minute steps from the full hour up to 25 past
+const PL_MINUTES: [&str; 6] = ["", "PIĘĆ", "DZIESIĘĆ", "KWADRANS", "DWADZIEŚCIA", "DWADZIEŚCIA PIĘĆ"];
+const EN_MINUTES: [&str; 6] = ["", "FIVE", "TEN", "QUARTER", "TWENTY", "TWENTY FIVE"];
+
+// Polish hours are feminine ordinals, "pierwsza" on the full hour and "za" before it,
+// "pierwszej" after "po" and "wpół do"
+const PL_HOURS: [&str; 12] = [
+    "DWUNASTA", "PIERWSZA", "DRUGA", "TRZECIA", "CZWARTA", "PIĄTA",
+    "SZÓSTA", "SIÓDMA", "ÓSMA", "DZIEWIĄTA", "DZIESIĄTA", "JEDENASTA",
+];
+const PL_HOURS_OBLIQUE: [&str; 12] = [
+    "DWUNASTEJ", "PIERWSZEJ", "DRUGIEJ", "TRZECIEJ", "CZWARTEJ", "PIĄTEJ",
+    "SZÓSTEJ", "SIÓDMEJ", "ÓSMEJ", "DZIEWIĄTEJ", "DZIESIĄTEJ", "JEDENASTEJ",
+];
+const EN_HOURS: [&str; 12] = [
+    "TWELVE", "ONE", "TWO", "THREE", "FOUR", "FIVE",
+    "SIX", "SEVEN", "EIGHT", "NINE", "TEN", "ELEVEN",
+];
+
+/// Time in words rounded to five minutes, scrolling by.
+pub struct WordsFace;
+
+impl ClockFace for WordsFace {
+    fn render(&self, datetime: &NaiveDateTime, context: &FaceContext, matrices: &mut MatrixDisplay) {
+        // Nearest five minutes, 10:58 already reads as eleven o'clock
+        let minutes = (datetime.hour() * 60 + datetime.minute() + 2) / 5 * 5;
+        let step = (minutes % 60 / 5) as usize;
+        let hour = (minutes / 60 % 12) as usize;
+        let next = (hour + 1) % 12;
+
+        let mut text: String<64> = String::new();
+        let _ = match settings::get().language {
+            Language::Polish => match step {
+                0 => text.push_str(PL_HOURS[hour]),
+                1..=5 => push_all(&mut text, &[PL_MINUTES[step], " PO ", PL_HOURS_OBLIQUE[hour]]),
+                6 => push_all(&mut text, &["WPÓŁ DO ", PL_HOURS_OBLIQUE[next]]),
+                _ => push_all(&mut text, &["ZA ", PL_MINUTES[12 - step], " ", PL_HOURS[next]]),
+            },
+            Language::English => match step {
+                0 => push_all(&mut text, &[EN_HOURS[hour], " O'CLOCK"]),
+                1..=5 => push_all(&mut text, &[EN_MINUTES[step], " PAST ", EN_HOURS[hour]]),
+                6 => push_all(&mut text, &["HALF PAST ", EN_HOURS[hour]]),
+                _ => push_all(&mut text, &[EN_MINUTES[12 - step], " TO ", EN_HOURS[next]]),
+            },
+        };
+        let _ = text.push_str("    ");
+
+        matrices.set_looped_text(&text, context.scroll_offset());
+    }
+}
+
+fn push_all(text: &mut String<64>, parts: &[&str]) -> Result<(), ()> {
+    parts.iter().try_for_each(|part| text.push_str(part))
+}
@@ -0,0 +1,212 @@
+use crate::settings::{self, Language};
+
+/// Something shown on the display in words. Menus, choices and animations refer to
+/// these, the language pack chosen in the settings turns them into text.
+#[derive(Clone, Copy)]
+pub enum Text {
+    // Messages, four characters so they fit the display without scrolling
+    Menu,
+    Back,
+    SetTime,
+    SwitchedOn,
+    SwitchedOff,
+
+    // Menu labels, scrolled when they do not fit
+    Time,
+    Date,
+    Format,
+    Hours,
+    Face,
+    Language,
+    Alarm,
+    Diagnostics,
+    Light,
+    Night,
+    Rotate,
+    Timeout,
+    Enabled,
+    Mode,
+    Schedule,
+    Sensor,
+    Dark,
+    Bright,
+    Level,
+    Start,
+    From,
+    To,
+    Idle,
+    Wake,
+    Dwell,
+    AlternateDays,
+
+    // Choice options, four characters at most
+    ModeSchedule,
+    ModeSensor,
+    IdleOff,
+    IdleDot,
+    DateLong,
+    FaceDigits,
+    FaceBinary,
+    FaceBar,
+    FaceRoman,
+    FaceWords,
+    FaceTime,
+    FaceDate,
+    FaceYear,
+    FaceWeekday,
+    FaceTemperature,
+
+    /// Short weekday name, 0 is Monday
+    Weekday(u8),
+    /// Same in every language, codes like "B1" or "DDMM"
+    Fixed(&'static str),
+}
+
+/// Words of one language.
+pub struct Pack {
+    /// Monday first
+    pub weekdays: [&'static str; 7],
+    pub months: [&'static str; 12],
+    words: fn(Text) -> &'static str,
+}
+
+static POLISH: Pack = Pack {
+    weekdays: ["PON", "WTO", "ŚRO", "CZW", "PIĄ", "SOB", "NIE"],
+    months: ["STY", "LUT", "MAR", "KWI", "MAJ", "CZE", "LIP", "SIE", "WRZ", "PAŹ", "LIS", "GRU"],
+    words: polish,
+};
+
+static ENGLISH: Pack = Pack {
+    weekdays: ["MON", "TUE", "WED", "THU", "FRI", "SAT", "SUN"],
+    months: ["JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC"],
+    words: english,
+};
+
+/// Pack of the language chosen in the settings.
+pub fn pack() -> &'static Pack {
+    match settings::get().language {
+        Language::Polish => &POLISH,
+        Language::English => &ENGLISH,
+    }
+}
+
+pub fn text(text: Text) -> &'static str {
+    let pack = pack();
+    match text {
+        Text::Weekday(day) => pack.weekdays[day as usize % 7],
+        Text::Fixed(text) => text,
+        text => (pack.words)(text),
+    }
+}
+
+fn polish(text: Text) -> &'static str {
+    match text {
+        Text::Menu => "MENU",
+        Text::Back => "WRÓĆ",
+        Text::SetTime => "UST!",
+        Text::SwitchedOn => " WŁ!",
+        Text::SwitchedOff => "WYŁ!",
+
+        Text::Time => "CZAS",
+        Text::Date => "DATA",
+        Text::Format => "FORMAT",
+        Text::Hours => "GODZINY",
+        Text::Face => "TARCZA",
+        Text::Language => "JĘZYK",
+        Text::Alarm => "BUDZIK",
+        Text::Diagnostics => "DIAG",
+        Text::Light => "JASNOŚĆ",
+        Text::Night => "NOC",
+        Text::Rotate => "ROTACJA",
+        Text::Timeout => "LIMIT",
+        Text::Enabled => "WŁ",
+        Text::Mode => "TRYB",
+        Text::Schedule => "PLAN",
+        Text::Sensor => "CZUJNIK",
+        Text::Dark => "CIEMNO",
+        Text::Bright => "JASNO",
+        Text::Level => "POZIOM",
+        Text::Start => "START",
+        Text::From => "OD",
+        Text::To => "DO",
+        Text::Idle => "SPOCZYNEK",
+        Text::Wake => "POKAZ",
+        Text::Dwell => "TRWANIE",
+        Text::AlternateDays => "DNI A",
+
+        Text::ModeSchedule => "CZAS",
+        Text::ModeSensor => "AUTO",
+        Text::IdleOff => " WYŁ",
+        Text::IdleDot => " PKT",
+        Text::DateLong => "DŁUG",
+        Text::FaceDigits => "CYFR",
+        Text::FaceBinary => " BIN",
+        Text::FaceBar => " PAS",
+        Text::FaceRoman => "RZYM",
+        Text::FaceWords => "SŁOW",
+        Text::FaceTime => "CZAS",
+        Text::FaceDate => "DATA",
+        Text::FaceYear => " ROK",
+        Text::FaceWeekday => " DZ.",
+        Text::FaceTemperature => "TEMP",
+
+        // Resolved in `text`
+        Text::Weekday(_) | Text::Fixed(_) => "",
+    }
+}
+
+fn english(text: Text) -> &'static str {
+    match text {
+        Text::Menu => "MENU",
+        Text::Back => "BACK",
+        Text::SetTime => "SET!",
+        Text::SwitchedOn => " ON!",
+        Text::SwitchedOff => "OFF!",
+
+        Text::Time => "TIME",
+        Text::Date => "DATE",
+        Text::Format => "FORMAT",
+        Text::Hours => "HOURS",
+        Text::Face => "FACE",
+        Text::Language => "LANG",
+        Text::Alarm => "ALARM",
+        Text::Diagnostics => "DIAG",
+        Text::Light => "LIGHT",
+        Text::Night => "NIGHT",
+        Text::Rotate => "ROTATE",
+        Text::Timeout => "TIMEOUT",
+        Text::Enabled => "ON",
+        Text::Mode => "MODE",
+        Text::Schedule => "SCHEDULE",
+        Text::Sensor => "SENSOR",
+        Text::Dark => "DARK",
+        Text::Bright => "BRIGHT",
+        Text::Level => "LEVEL",
+        Text::Start => "START",
+        Text::From => "FROM",
+        Text::To => "TO",
+        Text::Idle => "IDLE",
+        Text::Wake => "WAKE",
+        Text::Dwell => "DWELL",
+        Text::AlternateDays => "A DAYS",
+
+        Text::ModeSchedule => "TIME",
+        Text::ModeSensor => "AUTO",
+        Text::IdleOff => " OFF",
+        Text::IdleDot => " DOT",
+        Text::DateLong => "LONG",
+        Text::FaceDigits => "DIGI",
+        Text::FaceBinary => " BIN",
+        Text::FaceBar => " BAR",
+        Text::FaceRoman => "ROMA",
+        Text::FaceWords => "WORD",
+        Text::FaceTime => "TIME",
+        Text::FaceDate => "DATE",
+        Text::FaceYear => "YEAR",
+        Text::FaceWeekday => " DAY",
+        Text::FaceTemperature => "TEMP",
+
+        // Resolved in `text`
+        Text::Weekday(_) | Text::Fixed(_) => "",
+    }
+}
@@ -5,52 +5,96 @@ use defmt::*;
 use embassy_executor::Spawner;
 use embassy_stm32::Config;
 use ds1307::Ds1307;
+use embassy_stm32::adc::Adc;
 use embassy_stm32::dma::NoDma;
+use embassy_stm32::flash::Flash;
+#[cfg(any(feature = "display-max7219", feature = "display-max7219-7seg"))]
 use embassy_stm32::gpio::{Level, Output, Speed};
 use embassy_stm32::i2c::I2c;
+#[cfg(any(feature = "display-max7219", feature = "display-max7219-7seg"))]
+use embassy_stm32::spi::{self, Spi};
 use embassy_stm32::time::Hertz;
 use embassy_stm32::{bind_interrupts, i2c, peripherals};
-use embassy_time::Timer;
-use max7219::*;
-use utils::{set_display_intensity, alarm::Alarm};
-use {defmt_rtt as _, panic_probe as _};
+use embassy_time::{Delay, Timer};
+use utils::{alarm::Alarm, brightness, backup_rtc::BackupRtc, rtc::{Rtc, I2C_FREQUENCY}, Display, DisplayBackend};
+use defmt_rtt as _;
 use embassy_stm32::timer::simple_pwm::{PwmPin, SimplePwm};
 use embassy_stm32::time::hz;
+use embassy_stm32::wdg::IndependentWatchdog;
+use utils::watchdog::{self, WATCHDOG_TIMEOUT_US};
+use utils::animation::{self, Easing, Frame, Keyframe, Timeline};
+use locale::Text;
 
 mod utils;
+mod error;
 mod clock;
+mod faces;
+mod locale;
 mod menu;
+mod panic;
+mod settings;
 
 
+/// Four full digits sliding in from the right, doubles as a check for dead pixels.
+static BOOT_SPLASH: &[Timeline] = &[
+    Timeline::new(&[
+        Keyframe::new(0, Frame::Scroll { text: Text::Fixed("8888"), offset: -32 }).eased(Easing::EaseIn),
+        Keyframe::new(600, Frame::Scroll { text: Text::Fixed("8888"), offset: 0 }),
+    ], 1000),
+];
+
 bind_interrupts!(struct Irqs {
     I2C1_EV => i2c::EventInterruptHandler<peripherals::I2C1>;
     I2C1_ER => i2c::ErrorInterruptHandler<peripherals::I2C1>;
 });
 
+#[cfg(any(feature = "display-ht16k33", feature = "display-ssd1306"))]
+bind_interrupts!(struct DisplayIrqs {
+    I2C2_EV => i2c::EventInterruptHandler<peripherals::I2C2>;
+    I2C2_ER => i2c::ErrorInterruptHandler<peripherals::I2C2>;
+});
+
 #[embassy_executor::main]
-async fn main(_spawner: Spawner) {
+async fn main(spawner: Spawner) {
     // Init stm32
     let config = Config::default();
     let p = embassy_stm32::init(config);
+    utils::diagnostics::record_reset_cause();
+    utils::diagnostics::log_crash_record();
 
     //  Giving time for rtc to reset
     Timer::after_millis(100).await;
     
 
     // Init display
-    let cs = Output::new(p.PB0, Level::Low, Speed::Low);
-    let clk = Output::new(p.PA5, Level::Low, Speed::Low);
-    let din = Output::new(p.PA7, Level::Low, Speed::Low);
-    
-
-    let mut display = MAX7219::from_pins(4, din, cs, clk).unwrap();
+    #[cfg(any(feature = "display-max7219", feature = "display-max7219-7seg"))]
+    let mut display = {
+        // SPI1 with DMA on PA5 (SCK) and PA7 (MOSI)
+        let cs = Output::new(p.PB0, Level::High, Speed::VeryHigh);
+        let mut spi_config = spi::Config::default();
+        spi_config.frequency = Hertz(1_000_000);
+        let spi = Spi::new_txonly(p.SPI1, p.PA5, p.PA7, p.DMA1_CH3, NoDma, spi_config);
+
+        #[cfg(feature = "display-max7219")]
+        let display = Display::new(spi, cs, utils::display_config::DISPLAY_CONFIG).await;
+        #[cfg(feature = "display-max7219-7seg")]
+        let display = Display::new(spi, cs).await;
+        display.unwrap()
+    };
+
+    #[cfg(any(feature = "display-ht16k33", feature = "display-ssd1306"))]
+    let mut display = {
+        // Separate bus, I2C1 belongs to the DS1307 and its recovery
+        let i2c = I2c::new(p.I2C2, p.PB10, p.PB11, DisplayIrqs, NoDma, NoDma,
+                Hertz(400_000), Default::default());
+        Display::new(i2c).unwrap()
+    };
     
     // Init RTC
     let i2c = I2c::new(p.I2C1, p.PB6, p.PB7, Irqs, NoDma, NoDma,
-            Hertz(100_000), Default::default());
+            I2C_FREQUENCY, Default::default());
 
-    let mut rtc = Ds1307::new(i2c);
-    rtc.set_running().unwrap();
+    let mut rtc = Rtc::new(Ds1307::new(i2c), BackupRtc::init());
 
 
     // Init buzzer
@@ -67,9 +111,32 @@ async fn main(_spawner: Spawner) {
     let buttons = utils::buttons::Buttons::new(p.PA1, p.PA2, p.PA3, p.PA4);
     // alarm.play_alarm(&buttons, &mut display).await;
 
-    display.power_on().unwrap();
-    let _ = set_display_intensity(&mut display, 4);
-
+    display.power_on().await.unwrap();
+
+    // Defaults stay in use when the settings page is corrupted, the error is shown once
+    if let Err(err) = settings::init(Flash::new_blocking(p.FLASH)) {
+        let mut matrices = utils::matrix_display::MatrixDisplay::new();
+        matrices.set_error(err);
+        matrices.display_update(&mut display).await;
+        Timer::after_millis(2000).await;
+    }
+    animation::play(BOOT_SPLASH, &mut utils::matrix_display::MatrixDisplay::new(), &mut display).await;
+    if let Ok(datetime) = rtc.datetime() {
+        brightness::follow_schedule(&datetime);
+    }
+
+    // Photoresistor divider, only followed when selected in the menu
+    let adc = Adc::new(p.ADC1, &mut Delay);
+    unwrap!(spawner.spawn(utils::light_sensor::sample(adc, p.PA0)));
+    unwrap!(spawner.spawn(utils::thermometer::measure(p.PB12)));
+
+    let wdg = IndependentWatchdog::new(p.IWDG, WATCHDOG_TIMEOUT_US);
+    unwrap!(spawner.spawn(watchdog::supervise(wdg)));
+
+    // Battery died or oscillator was halted, ask for the time before showing it
+    if !rtc.check_time() {
+        menu::setup_wizard(&mut rtc, &mut display, &buttons, &mut alarm).await;
+    }
 
     loop {      
         info!("Main");
@@ -0,0 +1,112 @@
+use core::fmt::Write;
+
+use defmt::info;
+use embassy_time::{Duration, Instant, Timer};
+use heapless::String;
+
+use crate::utils::{buttons::Buttons, diagnostics, matrix_display::MatrixDisplay, Display, Mode};
+use super::framework::{timed_out, Outcome};
+use super::menu_utils::display_menu;
+
+// The crash location scrolls one column per step
+const SCROLL_STEP: Duration = Duration::from_millis(40);
+const REFRESH_MS: u64 = 20;
+
+#[derive(Clone, Copy)]
+enum DiagPage {
+    RecoveryAttempts,
+    RecoverySuccesses,
+    LastReset,
+    WatchdogResets,
+    LastCrash,
+}
+
+impl Mode for DiagPage {
+    fn next(&self) -> Self {
+        match self {
+            DiagPage::RecoveryAttempts => DiagPage::RecoverySuccesses,
+            DiagPage::RecoverySuccesses => DiagPage::LastReset,
+            DiagPage::LastReset => DiagPage::WatchdogResets,
+            DiagPage::WatchdogResets => DiagPage::LastCrash,
+            DiagPage::LastCrash => DiagPage::RecoveryAttempts,
+        }
+    }
+
+    fn prev(&self) -> Self {
+        match self {
+            DiagPage::RecoveryAttempts => DiagPage::LastCrash,
+            DiagPage::RecoverySuccesses => DiagPage::RecoveryAttempts,
+            DiagPage::LastReset => DiagPage::RecoverySuccesses,
+            DiagPage::WatchdogResets => DiagPage::LastReset,
+            DiagPage::LastCrash => DiagPage::WatchdogResets,
+        }
+    }
+}
+
+/// Read-only counters, up/down switch pages and exit goes back to the menu.
+pub async fn show_diagnostics<'a>(
+    display: &mut Display<'a>,
+    buttons: &Buttons<'a>,
+    matrices: &mut MatrixDisplay,
+) -> Outcome {
+    info!{"Diagnostics"}
+    let mut page = DiagPage::RecoveryAttempts;
+    let mut shown = Instant::now();
+
+    loop {
+        if timed_out(buttons) {
+            return Outcome::Timeout;
+        }
+
+        if buttons.mode_change(&mut page, true).await { shown = Instant::now(); }
+
+        display_page(&page, matrices, shown);
+        matrices.display_update(display).await;
+
+        if buttons.exit_is_low().await {
+            break;
+        }
+        Timer::after_millis(REFRESH_MS).await;
+    }
+
+    display_menu(matrices);
+    matrices.display_update(display).await;
+    Timer::after_millis(1000).await;
+
+    Outcome::Done
+}
+
+fn display_page(page: &DiagPage, matrices: &mut MatrixDisplay, shown: Instant) {
+    let (attempts, successes) = diagnostics::i2c_recovery_stats();
+    let mut text: String<8> = String::new();
+
+    if let DiagPage::LastCrash = page {
+        display_crash(matrices, shown);
+        return;
+    }
+
+    let _ = match page {
+        DiagPage::RecoveryAttempts => write!(text, "A{:>3}", attempts.min(999)),
+        DiagPage::RecoverySuccesses => write!(text, "S{:>3}", successes.min(999)),
+        DiagPage::LastReset => write!(text, "R:{}", diagnostics::reset_history()[0].code()),
+        DiagPage::WatchdogResets => write!(text, "W{:>3}", diagnostics::watchdog_resets().min(999)),
+        DiagPage::LastCrash => Ok(()),
+    };
+
+    matrices.set_text(&text);
+}
+
+// Scrolls the file name and line of the last panic, "C:--" when there was none
+fn display_crash(matrices: &mut MatrixDisplay, shown: Instant) {
+    let Some(record) = diagnostics::crash_record() else {
+        matrices.set_text("C:--");
+        return;
+    };
+
+    let file = record.file().rsplit('/').next().unwrap_or("");
+    let mut text: String<48> = String::new();
+    let _ = write!(text, "    {} {}    ", file.split('.').next().unwrap_or(""), record.line);
+
+    let scroll = (shown.elapsed().as_millis() / SCROLL_STEP.as_millis()) as usize % ((text.len() - 4) * 8);
+    matrices.set_scrolled_text(&text, scroll as isize);
+}
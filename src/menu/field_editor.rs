@@ -0,0 +1,178 @@
+use defmt::info;
+use heapless::Vec;
+
+use crate::utils::buttons::Buttons;
+use crate::utils::Mode;
+
+const MAX_VALUES: usize = 3;
+const MAX_SLOTS: usize = 6;
+// Holding a button only speeds up through ranges with at least this many values
+const ACCELERATE_FROM: i32 = 30;
+
+/// What happens when a value is stepped past the end of its range.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Overflow {
+    Wrap,
+    Clamp,
+}
+
+#[derive(Clone, Copy)]
+pub struct Range {
+    pub min: i32,
+    pub max: i32,
+    pub step: i32,
+    pub overflow: Overflow,
+}
+
+impl Range {
+    /// Steps of one, wrapping around at both ends.
+    pub const fn new(min: i32, max: i32) -> Self {
+        Range { min, max, step: 1, overflow: Overflow::Wrap }
+    }
+
+    pub const fn step(self, step: i32) -> Self {
+        Range { step, ..self }
+    }
+
+    pub const fn clamped(self) -> Self {
+        Range { overflow: Overflow::Clamp, ..self }
+    }
+
+    /// Number of values the range steps through.
+    pub fn count(&self) -> i32 {
+        (self.max - self.min) / self.step + 1
+    }
+
+    /// `value` moved by one step.
+    pub fn next(&self, value: i32, up: bool) -> i32 {
+        let moved = if up { value + self.step } else { value - self.step };
+        match self.overflow {
+            Overflow::Clamp => moved.clamp(self.min, self.max),
+            Overflow::Wrap => self.min + (moved - self.min).rem_euclid(self.max - self.min + 1),
+        }
+    }
+
+    /// `value` with one decimal digit moved, 0 being the ones. The digit rolls over
+    /// without carrying into its neighbours, values outside the range are skipped.
+    pub fn next_digit(&self, value: i32, digit: u32, up: bool) -> i32 {
+        let weight = 10i32.pow(digit);
+        let current = value / weight % 10;
+
+        (1..10)
+            .map(|offset| if up { (current + offset) % 10 } else { (current + 10 - offset) % 10 })
+            .map(|changed| value + (changed - current) * weight)
+            .find(|candidate| (self.min..=self.max).contains(candidate))
+            .unwrap_or(value)
+    }
+}
+
+/// One selectable part of the editor, e.g. the minutes of a time or the tens of a year.
+#[derive(Clone, Copy)]
+pub struct Slot {
+    /// Index of the value it changes
+    pub value: usize,
+    pub range: Range,
+    /// Digit edited on its own, `None` changes the whole value
+    pub digit: Option<u32>,
+    /// Matrices blinking while selected, bit 0 is the first matrix
+    pub blink: u8,
+}
+
+impl Slot {
+    pub const fn new(value: usize, range: Range, blink: u8) -> Self {
+        Slot { value, range, digit: None, blink }
+    }
+
+    pub const fn digit(self, digit: u32) -> Self {
+        Slot { digit: Some(digit), ..self }
+    }
+}
+
+// Currently selected slot, switched with exit and main clicks
+#[derive(Clone, Copy)]
+struct Selection {
+    index: usize,
+    count: usize,
+}
+
+impl Mode for Selection {
+    fn next(&self) -> Self {
+        Selection { index: (self.index + 1) % self.count, ..*self }
+    }
+
+    fn prev(&self) -> Self {
+        Selection { index: (self.index + self.count - 1) % self.count, ..*self }
+    }
+}
+
+/// Edits up to three numbers shown together, like hour and minute, one slot at a time.
+/// Up/down change the selected slot, exit and main clicks select the next or previous one.
+pub struct FieldEditor {
+    values: [i32; MAX_VALUES],
+    slots: Vec<Slot, MAX_SLOTS>,
+    selection: Selection,
+}
+
+impl FieldEditor {
+    pub fn new(values: &[i32], slots: &[Slot]) -> Self {
+        let mut editor = FieldEditor {
+            values: [0; MAX_VALUES],
+            slots: Vec::new(),
+            selection: Selection { index: 0, count: slots.len().clamp(1, MAX_SLOTS) },
+        };
+        for (stored, value) in editor.values.iter_mut().zip(values) {
+            *stored = *value;
+        }
+        for slot in slots.iter().take(MAX_SLOTS) {
+            let _ = editor.slots.push(*slot);
+        }
+        editor
+    }
+
+    pub fn value(&self, index: usize) -> i32 {
+        self.values[index]
+    }
+
+    pub fn selected(&self) -> Slot {
+        self.slots[self.selection.index]
+    }
+
+    /// Narrows the range of every slot of a value, e.g. the days of the current month.
+    pub fn set_max(&mut self, value: usize, max: i32) {
+        for slot in self.slots.iter_mut().filter(|slot| slot.value == value) {
+            slot.range.max = max;
+        }
+        self.values[value] = self.values[value].min(max);
+    }
+
+    /// Moves the selected slot by `steps` steps. Digits and short ranges always move
+    /// by one, a held button would only spin them around.
+    pub fn change(&mut self, up: bool, steps: u32) {
+        let slot = self.selected();
+        let steps = if slot.range.count() < ACCELERATE_FROM { 1 } else { steps };
+        let value = &mut self.values[slot.value];
+        *value = match slot.digit {
+            Some(digit) => slot.range.next_digit(*value, digit, up),
+            None => (0..steps).fold(*value, |value, _| slot.range.next(value, up)),
+        };
+        info!{"Value {}", *value};
+    }
+
+    /// Handles one poll of the buttons, true when the selection or a value changed.
+    /// Held up/down repeat, see `buttons::REPEAT`.
+    pub async fn poll(&mut self, buttons: &Buttons<'_>) -> bool {
+        let mut changed = buttons.mode_change(&mut self.selection, false).await;
+
+        let up = buttons.up_steps().await;
+        if up > 0 {
+            self.change(true, up);
+            changed = true;
+        }
+        let down = buttons.down_steps().await;
+        if down > 0 {
+            self.change(false, down);
+            changed = true;
+        }
+        changed
+    }
+}
@@ -0,0 +1,318 @@
+use core::fmt::Write;
+use core::ops::ControlFlow;
+
+use defmt::info;
+use ds1307::{Datelike, NaiveDate};
+use embassy_time::{Duration, Instant, Timer};
+use heapless::{String, Vec};
+
+use crate::clock::ClockMode;
+use crate::error::ClockError;
+use crate::locale::{self, Text};
+use crate::settings;
+use crate::utils::alarm::Alarm;
+use crate::utils::buttons::{Buttons, HoldTimer};
+use crate::utils::matrix_display::MatrixDisplay;
+use crate::utils::rtc::Rtc;
+use crate::utils::Display;
+use super::field_editor::{FieldEditor, Range, Slot};
+use super::menu_utils::{display_menu, off_display_info, on_display_info};
+use super::{blink_display, blink_matrices, days_in_month};
+
+const MENU_DEPTH: usize = 4;
+// Labels that do not fit scroll one column per step
+const LABEL_SCROLL_STEP: Duration = Duration::from_millis(40);
+
+/// Hardware the menu items read and write.
+pub struct MenuContext<'m, 'a> {
+    pub rtc: &'m mut Rtc<'a>,
+    pub display: &'m mut Display<'a>,
+    pub buttons: &'m Buttons<'a>,
+    pub alarm: &'m mut Alarm<'a>,
+    pub matrices: MatrixDisplay,
+}
+
+/// Where an item's value comes from and goes to. `set` is only called when the edit
+/// is accepted.
+#[derive(Clone, Copy)]
+pub struct Binding<T> {
+    pub get: fn(&mut MenuContext) -> Result<T, ClockError>,
+    pub set: fn(&mut MenuContext, T) -> Result<(), ClockError>,
+}
+
+/// Screens with their own loop, started from an `Item::Action`.
+#[derive(Clone, Copy)]
+pub enum Action {
+    Diagnostics,
+    LightReading,
+}
+
+#[derive(Clone, Copy)]
+pub struct NumberField {
+    /// Shown before the value, the value is right aligned in the rest of the display
+    pub prefix: &'static str,
+    pub range: Range,
+    pub value: Binding<i32>,
+}
+
+/// Menu entry. Trees of these are plain static data, the menu loop below handles
+/// navigation, editing and labels for all of them.
+#[derive(Clone, Copy)]
+pub enum Item {
+    Submenu { label: Text, items: &'static [Item] },
+    Action { label: Text, action: Action },
+    Toggle { label: Text, value: Binding<bool> },
+    Number { label: Text, field: NumberField },
+    /// Options are shown as they are, four characters at most
+    Choice { label: Text, options: &'static [Text], value: Binding<usize> },
+    /// Hour and minute
+    Time { label: Text, value: Binding<(u32, u32)> },
+    Date { label: Text, value: Binding<NaiveDate> },
+}
+
+impl Item {
+    /// Label in the language from the settings.
+    pub fn label(&self) -> &'static str {
+        let label = match self {
+            Item::Submenu { label, .. }
+            | Item::Action { label, .. }
+            | Item::Toggle { label, .. }
+            | Item::Number { label, .. }
+            | Item::Choice { label, .. }
+            | Item::Time { label, .. }
+            | Item::Date { label, .. } => label,
+        };
+        locale::text(*label)
+    }
+}
+
+pub enum Outcome {
+    Done,
+    /// Left alone for longer than the menu timeout, edits in progress are dropped
+    Timeout,
+}
+
+/// No button pressed for the menu timeout from the settings.
+pub fn timed_out(buttons: &Buttons<'_>) -> bool {
+    buttons.idle_for() > Duration::from_secs(settings::get().menu_timeout as u64)
+}
+
+/// Runs a menu tree. Up/down move between entries, main enters a submenu or edits
+/// the entry and exit goes back one level, leaving the menu from the top.
+pub async fn run(root: &'static [Item], ctx: &mut MenuContext<'_, '_>) -> Outcome {
+    let mut parents: Vec<(&'static [Item], usize), MENU_DEPTH> = Vec::new();
+    let mut items = root;
+    let mut index = 0;
+    let mut shown = Instant::now();
+
+    loop {
+        if timed_out(ctx.buttons) {
+            return Outcome::Timeout;
+        }
+
+        if ctx.buttons.up_is_low().await {
+            index = (index + 1) % items.len();
+            shown = Instant::now();
+        }
+        if ctx.buttons.down_is_low().await {
+            index = (index + items.len() - 1) % items.len();
+            shown = Instant::now();
+        }
+
+        display_label(&mut ctx.matrices, index, items[index].label(), shown);
+        ctx.matrices.display_update(ctx.display).await;
+
+        if ctx.buttons.main_is_low().await {
+            shown = Instant::now();
+            match items[index] {
+                Item::Submenu { items: children, .. } => {
+                    if parents.push((items, index)).is_ok() {
+                        items = children;
+                        index = 0;
+                    }
+                }
+                Item::Action { action, .. } => {
+                    if let Outcome::Timeout = super::run_action(action, ctx).await {
+                        return Outcome::Timeout;
+                    }
+                }
+                item => match edit(&item, ctx).await {
+                    Ok(Outcome::Done) => {}
+                    Ok(Outcome::Timeout) => return Outcome::Timeout,
+                    Err(err) => {
+                        ctx.matrices.set_error(err);
+                        ctx.matrices.display_update(ctx.display).await;
+                        Timer::after_millis(1000).await;
+                    }
+                },
+            }
+        }
+
+        if ctx.buttons.exit_is_low().await {
+            shown = Instant::now();
+            match parents.pop() {
+                Some((parent, position)) => {
+                    items = parent;
+                    index = position;
+                }
+                None => return Outcome::Done,
+            }
+        }
+    }
+}
+
+// "1:TIME" style label, scrolled around when it does not fit
+fn display_label(matrices: &mut MatrixDisplay, index: usize, label: &str, shown: Instant) {
+    let mut cycle: String<32> = String::new();
+    let _ = write!(cycle, "{}:{} ", index + 1, label);
+
+    // Counted in characters, Polish letters take two bytes
+    if cycle.chars().count() <= 5 {
+        matrices.set_text(&cycle);
+        return;
+    }
+
+    let offset = (shown.elapsed().as_millis() / LABEL_SCROLL_STEP.as_millis()) as usize;
+    matrices.set_looped_text(&cycle, offset);
+}
+
+// Hour and minute. The hour always steps through all 24, in 12-hour format that
+// reads 11, 12 with the PM marker, then 1
+const TIME_SLOTS: [Slot; 2] = [
+    Slot::new(0, Range::new(0, 23), 0b0011),
+    Slot::new(1, Range::new(0, 59), 0b1100),
+];
+
+// The DS1307 counts years from 2000 to 2099
+const YEARS: Range = Range::new(2000, 2099);
+
+// Day and month, then the year digit by digit. The day range follows the month
+const DATE_SLOTS: [Slot; 6] = [
+    Slot::new(0, Range::new(1, 31), 0b0011),
+    Slot::new(1, Range::new(1, 12), 0b1100),
+    Slot::new(2, YEARS, 0b0001).digit(3),
+    Slot::new(2, YEARS, 0b0010).digit(2),
+    Slot::new(2, YEARS, 0b0100).digit(1),
+    Slot::new(2, YEARS, 0b1000).digit(0),
+];
+
+/// Edits one item. Exit and main clicks move between the parts of the value, up/down
+/// change it, holding main accepts and holding exit leaves without saving.
+pub async fn edit(item: &Item, ctx: &mut MenuContext<'_, '_>) -> Result<Outcome, ClockError> {
+    let mut editor = match item {
+        Item::Toggle { value, .. } => FieldEditor::new(&[(value.get)(ctx)? as i32], &[Slot::new(0, Range::new(0, 1), 0)]),
+        Item::Number { field, .. } => {
+            // Only the value blinks, not the prefix
+            let blink = 0b1111u8.checked_shl(field.prefix.len() as u32).unwrap_or(0) & 0b1111;
+            FieldEditor::new(&[(field.value.get)(ctx)?], &[Slot::new(0, field.range, blink)])
+        }
+        Item::Choice { options, value, .. } => {
+            let range = Range::new(0, options.len() as i32 - 1);
+            FieldEditor::new(&[(value.get)(ctx)? as i32], &[Slot::new(0, range, 0)])
+        }
+        Item::Time { value, .. } => {
+            let (hour, minute) = (value.get)(ctx)?;
+            FieldEditor::new(&[hour as i32, minute as i32], &TIME_SLOTS)
+        }
+        Item::Date { value, .. } => {
+            let date = (value.get)(ctx)?;
+            FieldEditor::new(&[date.day() as i32, date.month() as i32, date.year()], &DATE_SLOTS)
+        }
+        Item::Submenu { .. } | Item::Action { .. } => return Ok(Outcome::Done),
+    };
+    info!{"Editing {}", item.label()}
+    limit_day(item, &mut editor);
+
+    let mut hold_accept = HoldTimer::default();
+    let mut hold_exit = HoldTimer::default();
+
+    // Restarted on every change so the new value is visible before it blinks
+    let mut shown = Instant::now();
+    loop {
+        if timed_out(ctx.buttons) {
+            return Ok(Outcome::Timeout);
+        }
+
+        if hold_accept.is_click() && hold_exit.is_click() {
+            if editor.poll(ctx.buttons).await {
+                limit_day(item, &mut editor);
+                shown = Instant::now();
+            }
+        } else {
+            shown = Instant::now();
+        }
+
+        display_value(item, &editor, shown, ctx).await?;
+
+        if let ControlFlow::Break(_) = ctx.buttons.button_hold(&mut hold_exit, false).await { break; }
+
+        if let ControlFlow::Break(_) = ctx.buttons.button_hold(&mut hold_accept, true).await {
+            store(item, &editor, ctx)?;
+            break;
+        }
+    }
+
+    display_menu(&mut ctx.matrices);
+    ctx.matrices.display_update(ctx.display).await;
+    Timer::after_millis(1000).await;
+
+    Ok(Outcome::Done)
+}
+
+// Keeps the day within the edited month and year
+fn limit_day(item: &Item, editor: &mut FieldEditor) {
+    if let Item::Date { .. } = item {
+        let days = days_in_month(editor.value(1) as u32, editor.value(2));
+        editor.set_max(0, days as i32);
+    }
+}
+
+fn edited_date(editor: &FieldEditor) -> Result<NaiveDate, ClockError> {
+    NaiveDate::from_ymd_opt(editor.value(2), editor.value(1) as u32, editor.value(0) as u32).ok_or(ClockError::InvalidDate)
+}
+
+async fn display_value(item: &Item, editor: &FieldEditor, shown: Instant, ctx: &mut MenuContext<'_, '_>) -> Result<(), ClockError> {
+    let matrices = &mut ctx.matrices;
+    let slot = editor.selected();
+
+    match item {
+        Item::Time { .. } => {
+            let datetime = NaiveDate::from_ymd_opt(2000, 1, 1)
+                .and_then(|date| date.and_hms_opt(editor.value(0) as u32, editor.value(1) as u32, 0))
+                .ok_or(ClockError::InvalidDate)?;
+            blink_display(&ClockMode::Time, datetime, slot.blink, matrices, shown, ctx.display).await;
+            return Ok(());
+        }
+        Item::Date { .. } => {
+            let datetime = edited_date(editor)?.and_hms_opt(0, 0, 0).ok_or(ClockError::InvalidDate)?;
+            let mode = if slot.value == 2 { ClockMode::Year } else { ClockMode::Date };
+            blink_display(&mode, datetime, slot.blink, matrices, shown, ctx.display).await;
+            return Ok(());
+        }
+        Item::Toggle { .. } if editor.value(0) != 0 => on_display_info(matrices),
+        Item::Toggle { .. } => off_display_info(matrices),
+        Item::Choice { options, .. } => matrices.set_text(locale::text(options[editor.value(0) as usize])),
+        Item::Number { field, .. } => {
+            let mut text: String<8> = String::new();
+            let width = 4usize.saturating_sub(field.prefix.len());
+            let _ = write!(text, "{}{:>width$}", field.prefix, editor.value(0), width = width);
+            matrices.set_text(&text);
+            blink_matrices(matrices, slot.blink, shown);
+        }
+        Item::Submenu { .. } | Item::Action { .. } => {}
+    }
+
+    matrices.display_update(ctx.display).await;
+    Ok(())
+}
+
+fn store(item: &Item, editor: &FieldEditor, ctx: &mut MenuContext<'_, '_>) -> Result<(), ClockError> {
+    match item {
+        Item::Toggle { value, .. } => (value.set)(ctx, editor.value(0) != 0),
+        Item::Number { field, .. } => (field.value.set)(ctx, editor.value(0)),
+        Item::Choice { value, .. } => (value.set)(ctx, editor.value(0) as usize),
+        Item::Time { value, .. } => (value.set)(ctx, (editor.value(0) as u32, editor.value(1) as u32)),
+        Item::Date { value, .. } => (value.set)(ctx, edited_date(editor)?),
+        Item::Submenu { .. } | Item::Action { .. } => Ok(()),
+    }
+}
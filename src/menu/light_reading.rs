@@ -0,0 +1,42 @@
+use core::fmt::Write;
+
+use defmt::info;
+use embassy_time::Timer;
+use heapless::String;
+
+use crate::utils::{buttons::Buttons, light_sensor, matrix_display::MatrixDisplay, Display};
+use super::framework::{timed_out, Outcome};
+use super::menu_utils::display_menu;
+
+const REFRESH_MS: u64 = 100;
+
+/// Live filtered light reading, 0 to 4095, until exit is pressed or the menu times out.
+pub async fn show_light_reading<'a>(
+    display: &mut Display<'a>,
+    buttons: &Buttons<'a>,
+    matrices: &mut MatrixDisplay,
+) -> Outcome {
+    info!{"Light reading"}
+
+    loop {
+        if timed_out(buttons) {
+            return Outcome::Timeout;
+        }
+
+        let mut text: String<8> = String::new();
+        let _ = write!(text, "{:>4}", light_sensor::reading());
+        matrices.set_text(&text);
+        matrices.display_update(display).await;
+
+        if buttons.exit_is_low().await {
+            break;
+        }
+        Timer::after_millis(REFRESH_MS).await;
+    }
+
+    display_menu(matrices);
+    matrices.display_update(display).await;
+    Timer::after_millis(1000).await;
+
+    Outcome::Done
+}
@@ -0,0 +1,396 @@
+use ds1307::{NaiveDate, Timelike};
+
+use crate::error::ClockError;
+use crate::locale::Text;
+use crate::settings::{
+    self, BrightnessBand, BrightnessMode, DateFormat, Face, HourFormat, Language, NightIdle, Settings, TimeFace,
+    BRIGHTNESS_BANDS, DEFAULT_LEVEL, MAX_DWELL_SECONDS, MAX_LEVEL, MAX_MENU_TIMEOUT, MAX_WAKE_SECONDS, MIN_MENU_TIMEOUT,
+};
+use crate::utils::light_sensor;
+use super::field_editor::Range;
+use super::framework::{Action, Binding, Item, MenuContext, NumberField};
+
+// Brightness levels stop at the ends instead of jumping from darkest to brightest
+const LEVELS: Range = Range::new(0, MAX_LEVEL as i32).clamped();
+
+/// Main menu, entered from the clock.
+pub static MENU: &[Item] = &[
+    RTC_TIME,
+    RTC_DATE,
+    Item::Submenu { label: Text::Format, items: FORMAT },
+    Item::Submenu { label: Text::Alarm, items: ALARM },
+    Item::Action { label: Text::Diagnostics, action: Action::Diagnostics },
+    Item::Submenu { label: Text::Light, items: LIGHT },
+    Item::Submenu { label: Text::Night, items: NIGHT },
+    Item::Submenu { label: Text::Rotate, items: ROTATE },
+    // Seconds without a press before a menu returns to the clock
+    Item::Number {
+        label: Text::Timeout,
+        field: NumberField {
+            prefix: "T",
+            range: Range::new(MIN_MENU_TIMEOUT as i32, MAX_MENU_TIMEOUT as i32).step(10).clamped(),
+            value: Binding {
+                get: |_| Ok(settings::get().menu_timeout as i32),
+                set: |_, seconds| settings::update(|settings| settings.menu_timeout = seconds as u8),
+            },
+        },
+    },
+];
+
+pub const RTC_TIME: Item = Item::Time {
+    label: Text::Time,
+    value: Binding {
+        get: |ctx| {
+            let datetime = ctx.rtc.datetime()?;
+            Ok((datetime.hour(), datetime.minute()))
+        },
+        // Seconds start again from zero
+        set: |ctx, (hour, minute)| {
+            let datetime = ctx.rtc.datetime()?;
+            let changed = datetime.date().and_hms_opt(hour, minute, 0).ok_or(ClockError::InvalidDate)?;
+            Ok(ctx.rtc.set_datetime(&changed)?)
+        },
+    },
+};
+
+pub const RTC_DATE: Item = Item::Date {
+    label: Text::Date,
+    value: Binding {
+        get: |ctx| Ok(ctx.rtc.datetime()?.date()),
+        set: |ctx, date: NaiveDate| {
+            let datetime = ctx.rtc.datetime()?;
+            Ok(ctx.rtc.set_datetime(&date.and_time(datetime.time()))?)
+        },
+    },
+};
+
+// Options in the order of each setting's `index()`
+static FORMAT: &[Item] = &[
+    Item::Choice {
+        label: Text::Hours,
+        options: &[Text::Fixed(" 24H"), Text::Fixed(" 12H")],
+        value: Binding {
+            get: |_| Ok(settings::get().hour_format.index()),
+            set: |_, index| settings::update(|settings| settings.hour_format = HourFormat::from_index(index)),
+        },
+    },
+    Item::Choice {
+        label: Text::Date,
+        options: &[Text::Fixed("DDMM"), Text::Fixed("MMDD"), Text::Fixed(" ISO"), Text::DateLong],
+        value: Binding {
+            get: |_| Ok(settings::get().date_format.index()),
+            set: |_, index| settings::update(|settings| settings.date_format = DateFormat::from_index(index)),
+        },
+    },
+    Item::Choice {
+        label: Text::Face,
+        options: &[Text::FaceDigits, Text::FaceBinary, Text::FaceBar, Text::FaceRoman, Text::FaceWords],
+        value: Binding {
+            get: |_| Ok(settings::get().time_face.index()),
+            set: |_, index| settings::update(|settings| settings.time_face = TimeFace::from_index(index)),
+        },
+    },
+    Item::Choice {
+        label: Text::Language,
+        options: &[Text::Fixed(" POL"), Text::Fixed(" ENG")],
+        value: Binding {
+            get: |_| Ok(settings::get().language.index()),
+            set: |_, index| settings::update(|settings| settings.language = Language::from_index(index)),
+        },
+    },
+];
+
+static ALARM: &[Item] = &[
+    Item::Toggle {
+        label: Text::Enabled,
+        value: Binding {
+            get: |ctx| Ok(ctx.alarm.is_enable()),
+            set: |ctx, enabled| {
+                ctx.alarm.enable(enabled);
+                Ok(())
+            },
+        },
+    },
+    // Setting the time also arms the alarm
+    Item::Time {
+        label: Text::Time,
+        value: Binding {
+            get: |ctx| Ok((ctx.alarm.get_hour(), ctx.alarm.get_minute())),
+            set: |ctx, (hour, minute)| {
+                ctx.alarm.update_time(hour, minute);
+                ctx.alarm.enable(true);
+                Ok(())
+            },
+        },
+    },
+];
+
+static LIGHT: &[Item] = &[
+    Item::Choice {
+        label: Text::Mode,
+        options: &[Text::ModeSchedule, Text::ModeSensor],
+        value: Binding {
+            get: |_| Ok(settings::get().brightness_mode.index()),
+            set: |_, index| settings::update(|settings| settings.brightness_mode = BrightnessMode::from_index(index)),
+        },
+    },
+    Item::Submenu { label: Text::Schedule, items: SCHEDULE },
+    Item::Action { label: Text::Sensor, action: Action::LightReading },
+    // Changing a level also takes the current reading as its calibration point,
+    // so cover or light the sensor before editing
+    Item::Number {
+        label: Text::Dark,
+        field: NumberField {
+            prefix: "D:",
+            range: LEVELS,
+            value: Binding {
+                get: |_| Ok(settings::get().light.dark_level as i32),
+                set: |_, level| settings::update(|settings| {
+                    settings.light.dark_level = level as u8;
+                    settings.light.dark_reading = light_sensor::reading();
+                }),
+            },
+        },
+    },
+    Item::Number {
+        label: Text::Bright,
+        field: NumberField {
+            prefix: "B:",
+            range: LEVELS,
+            value: Binding {
+                get: |_| Ok(settings::get().light.bright_level as i32),
+                set: |_, level| settings::update(|settings| {
+                    settings.light.bright_level = level as u8;
+                    settings.light.bright_reading = light_sensor::reading();
+                }),
+            },
+        },
+    },
+];
+
+// Regular bands B1 to B4, alternate bands A1 to A4 and the weekdays using them
+static SCHEDULE: &[Item] = &[
+    Item::Submenu { label: Text::Fixed("B1"), items: &BAND_1 },
+    Item::Submenu { label: Text::Fixed("B2"), items: &BAND_2 },
+    Item::Submenu { label: Text::Fixed("B3"), items: &BAND_3 },
+    Item::Submenu { label: Text::Fixed("B4"), items: &BAND_4 },
+    Item::Submenu { label: Text::Fixed("A1"), items: &ALTERNATE_1 },
+    Item::Submenu { label: Text::Fixed("A2"), items: &ALTERNATE_2 },
+    Item::Submenu { label: Text::Fixed("A3"), items: &ALTERNATE_3 },
+    Item::Submenu { label: Text::Fixed("A4"), items: &ALTERNATE_4 },
+    Item::Submenu { label: Text::AlternateDays, items: ALTERNATE_DAYS },
+];
+
+static BAND_1: [Item; 3] = band_items::<0>();
+static BAND_2: [Item; 3] = band_items::<1>();
+static BAND_3: [Item; 3] = band_items::<2>();
+static BAND_4: [Item; 3] = band_items::<3>();
+static ALTERNATE_1: [Item; 3] = band_items::<4>();
+static ALTERNATE_2: [Item; 3] = band_items::<5>();
+static ALTERNATE_3: [Item; 3] = band_items::<6>();
+static ALTERNATE_4: [Item; 3] = band_items::<7>();
+
+static ALTERNATE_DAYS: &[Item] = &[
+    alternate_day::<0>(),
+    alternate_day::<1>(),
+    alternate_day::<2>(),
+    alternate_day::<3>(),
+    alternate_day::<4>(),
+    alternate_day::<5>(),
+    alternate_day::<6>(),
+];
+
+// Bands 0 to 3 are the regular ones, 4 to 7 the alternate ones
+fn band<const BAND: usize>(settings: &mut Settings) -> &mut BrightnessBand {
+    match BAND.checked_sub(BRIGHTNESS_BANDS) {
+        Some(alternate) => &mut settings.brightness.alternate[alternate],
+        None => &mut settings.brightness.bands[BAND],
+    }
+}
+
+const fn band_items<const BAND: usize>() -> [Item; 3] {
+    [
+        Item::Toggle {
+            label: Text::Enabled,
+            value: Binding { get: band_on::<BAND>, set: set_band_on::<BAND> },
+        },
+        // Editing the level of an unused band turns it on
+        Item::Number {
+            label: Text::Level,
+            field: NumberField {
+                prefix: "L:",
+                range: LEVELS,
+                value: Binding { get: band_level::<BAND>, set: set_band_level::<BAND> },
+            },
+        },
+        Item::Time {
+            label: Text::Start,
+            value: Binding { get: band_start::<BAND>, set: set_band_start::<BAND> },
+        },
+    ]
+}
+
+fn band_on<const BAND: usize>(_: &mut MenuContext) -> Result<bool, ClockError> {
+    Ok(band::<BAND>(&mut settings::get()).level.is_some())
+}
+
+fn set_band_on<const BAND: usize>(_: &mut MenuContext, on: bool) -> Result<(), ClockError> {
+    settings::update(|settings| {
+        let band = band::<BAND>(settings);
+        band.level = match (on, band.level) {
+            (false, _) => None,
+            (true, None) => Some(DEFAULT_LEVEL),
+            (true, level) => level,
+        };
+    })
+}
+
+fn band_level<const BAND: usize>(_: &mut MenuContext) -> Result<i32, ClockError> {
+    Ok(band::<BAND>(&mut settings::get()).level.unwrap_or(DEFAULT_LEVEL) as i32)
+}
+
+fn set_band_level<const BAND: usize>(_: &mut MenuContext, level: i32) -> Result<(), ClockError> {
+    settings::update(|settings| band::<BAND>(settings).level = Some(level as u8))
+}
+
+fn band_start<const BAND: usize>(_: &mut MenuContext) -> Result<(u32, u32), ClockError> {
+    let band = *band::<BAND>(&mut settings::get());
+    Ok((band.start_hour as u32, band.start_minute as u32))
+}
+
+fn set_band_start<const BAND: usize>(_: &mut MenuContext, (hour, minute): (u32, u32)) -> Result<(), ClockError> {
+    settings::update(|settings| {
+        let band = band::<BAND>(settings);
+        band.start_hour = hour as u8;
+        band.start_minute = minute as u8;
+    })
+}
+
+const fn alternate_day<const DAY: u8>() -> Item {
+    Item::Toggle {
+        label: Text::Weekday(DAY),
+        value: Binding { get: alternate_day_on::<DAY>, set: set_alternate_day::<DAY> },
+    }
+}
+
+fn alternate_day_on<const DAY: u8>(_: &mut MenuContext) -> Result<bool, ClockError> {
+    Ok(settings::get().brightness.alternate_days & (1 << DAY) != 0)
+}
+
+fn set_alternate_day<const DAY: u8>(_: &mut MenuContext, alternate: bool) -> Result<(), ClockError> {
+    settings::update(|settings| {
+        let days = &mut settings.brightness.alternate_days;
+        *days = if alternate { *days | (1 << DAY) } else { *days & !(1 << DAY) };
+    })
+}
+
+static NIGHT: &[Item] = &[
+    Item::Toggle {
+        label: Text::Enabled,
+        value: Binding {
+            get: |_| Ok(settings::get().night.enabled),
+            set: |_, enabled| settings::update(|settings| settings.night.enabled = enabled),
+        },
+    },
+    Item::Time {
+        label: Text::From,
+        value: Binding {
+            get: |_| {
+                let night = settings::get().night;
+                Ok((night.start_hour as u32, night.start_minute as u32))
+            },
+            set: |_, (hour, minute)| settings::update(|settings| {
+                settings.night.start_hour = hour as u8;
+                settings.night.start_minute = minute as u8;
+            }),
+        },
+    },
+    Item::Time {
+        label: Text::To,
+        value: Binding {
+            get: |_| {
+                let night = settings::get().night;
+                Ok((night.end_hour as u32, night.end_minute as u32))
+            },
+            set: |_, (hour, minute)| settings::update(|settings| {
+                settings.night.end_hour = hour as u8;
+                settings.night.end_minute = minute as u8;
+            }),
+        },
+    },
+    Item::Choice {
+        label: Text::Idle,
+        options: &[Text::IdleOff, Text::IdleDot],
+        value: Binding {
+            get: |_| Ok(settings::get().night.idle.index()),
+            set: |_, index| settings::update(|settings| settings.night.idle = NightIdle::from_index(index)),
+        },
+    },
+    // Seconds a press shows the time, 0 keeps the display dark
+    Item::Number {
+        label: Text::Wake,
+        field: NumberField {
+            prefix: "W:",
+            range: Range::new(0, MAX_WAKE_SECONDS as i32).step(5).clamped(),
+            value: Binding {
+                get: |_| Ok(settings::get().night.wake_seconds as i32),
+                set: |_, seconds| settings::update(|settings| settings.night.wake_seconds = seconds as u8),
+            },
+        },
+    },
+];
+
+// Faces shown one after another in slot order, a dwell of 0 skips the slot
+static ROTATE: &[Item] = &[
+    Item::Toggle {
+        label: Text::Enabled,
+        value: Binding {
+            get: |_| Ok(settings::get().rotation.enabled),
+            set: |_, enabled| settings::update(|settings| settings.rotation.enabled = enabled),
+        },
+    },
+    Item::Submenu { label: Text::Fixed("S1"), items: &SLOT_1 },
+    Item::Submenu { label: Text::Fixed("S2"), items: &SLOT_2 },
+    Item::Submenu { label: Text::Fixed("S3"), items: &SLOT_3 },
+    Item::Submenu { label: Text::Fixed("S4"), items: &SLOT_4 },
+];
+
+static SLOT_1: [Item; 2] = slot_items::<0>();
+static SLOT_2: [Item; 2] = slot_items::<1>();
+static SLOT_3: [Item; 2] = slot_items::<2>();
+static SLOT_4: [Item; 2] = slot_items::<3>();
+
+const fn slot_items<const SLOT: usize>() -> [Item; 2] {
+    [
+        // Same order as `Face`
+        Item::Choice {
+            label: Text::Face,
+            options: &[Text::FaceTime, Text::FaceDate, Text::FaceYear, Text::FaceWeekday, Text::FaceTemperature],
+            value: Binding { get: slot_face::<SLOT>, set: set_slot_face::<SLOT> },
+        },
+        Item::Number {
+            label: Text::Dwell,
+            field: NumberField {
+                prefix: "D",
+                range: Range::new(0, MAX_DWELL_SECONDS as i32).step(5).clamped(),
+                value: Binding { get: slot_dwell::<SLOT>, set: set_slot_dwell::<SLOT> },
+            },
+        },
+    ]
+}
+
+fn slot_face<const SLOT: usize>(_: &mut MenuContext) -> Result<usize, ClockError> {
+    Ok(settings::get().rotation.slots[SLOT].face.index())
+}
+
+fn set_slot_face<const SLOT: usize>(_: &mut MenuContext, index: usize) -> Result<(), ClockError> {
+    settings::update(|settings| settings.rotation.slots[SLOT].face = Face::from_index(index))
+}
+
+fn slot_dwell<const SLOT: usize>(_: &mut MenuContext) -> Result<i32, ClockError> {
+    Ok(settings::get().rotation.slots[SLOT].dwell_seconds as i32)
+}
+
+fn set_slot_dwell<const SLOT: usize>(_: &mut MenuContext, seconds: i32) -> Result<(), ClockError> {
+    settings::update(|settings| settings.rotation.slots[SLOT].dwell_seconds = seconds as u8)
+}
@@ -0,0 +1,121 @@
+use core::panic::PanicInfo;
+
+use cortex_m::peripheral::SCB;
+use embassy_stm32::pac;
+
+use crate::utils::backup_rtc::BackupRtc;
+use crate::utils::diagnostics;
+
+/// Saves a crash record, scrolls "PANIC" with the location over the display and reboots.
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    cortex_m::interrupt::disable();
+
+    let timestamp = if pac::RCC.bdcr().read().rtcen() { BackupRtc::counter() } else { 0 };
+    diagnostics::record_crash(info, timestamp);
+    defmt::error!("{}", defmt::Display2Format(info));
+
+    // The I2C panels would need their bus driver brought up again from here, on those
+    // the crash record is only shown in the diagnostics menu after the reboot
+    #[cfg(any(feature = "display-max7219", feature = "display-max7219-7seg"))]
+    crash_screen::show(info);
+
+    SCB::sys_reset()
+}
+
+// Only the MAX7219 panels, the chain can be bit-banged on the SPI1 pins
+#[cfg(any(feature = "display-max7219", feature = "display-max7219-7seg"))]
+mod crash_screen {
+    use core::fmt::Write;
+    use core::panic::PanicInfo;
+
+    use embassy_stm32::gpio::{Level, Output, Speed};
+    use embassy_stm32::peripherals::{PA5, PA7, PB0};
+    use heapless::String;
+    use max7219::MAX7219;
+
+    #[cfg(feature = "display-max7219")]
+    use crate::utils::display_config::DISPLAY_CONFIG;
+    #[cfg(feature = "display-max7219")]
+    use crate::utils::matrix_display::MatrixDisplay;
+    #[cfg(feature = "display-max7219-7seg")]
+    use heapless::Vec;
+    #[cfg(feature = "display-max7219-7seg")]
+    use max7219::DecodeMode;
+
+    // Core runs from the 8 MHz HSI, about 30 ms per scrolled column
+    const SCROLL_STEP_CYCLES: u32 = 240_000;
+
+    pub fn show(info: &PanicInfo) {
+        let mut text: String<16> = String::new();
+        let _ = write!(text, "PANIC L{}", info.location().map_or(0, |location| location.line()));
+
+        // Main task owned the pins, it will never run again. SPI and DMA may be stopped
+        // halfway through a transfer, so the chain is bit-banged instead
+        let (din, cs, clk) = unsafe {
+            (
+                Output::new(PA7::steal(), Level::Low, Speed::Low),
+                Output::new(PB0::steal(), Level::Low, Speed::Low),
+                Output::new(PA5::steal(), Level::Low, Speed::Low),
+            )
+        };
+
+        #[cfg(feature = "display-max7219")]
+        let modules = DISPLAY_CONFIG.modules;
+        #[cfg(feature = "display-max7219-7seg")]
+        let modules = 1;
+
+        let Ok(mut display) = MAX7219::from_pins(modules, din, cs, clk) else { return };
+        if display.power_on().is_err() {
+            return;
+        }
+
+        #[cfg(feature = "display-max7219")]
+        {
+            let mut matrices = MatrixDisplay::new();
+            for offset in 0..(text.len() * 8) as isize {
+                matrices.set_scrolled_text(&text, offset);
+                let chain = DISPLAY_CONFIG.map_frame(&matrices.frame());
+                for (module, bitmap) in chain.iter().enumerate().take(DISPLAY_CONFIG.modules) {
+                    if display.write_raw(module, bitmap).is_err() {
+                        return;
+                    }
+                }
+                cortex_m::asm::delay(SCROLL_STEP_CYCLES);
+            }
+        }
+
+        // Whole digits move, so each step stays up as long as a glyph does on the matrices
+        #[cfg(feature = "display-max7219-7seg")]
+        {
+            if display.set_decode_mode(0, DecodeMode::NoDecode).is_err() {
+                return;
+            }
+            let codes: Vec<u8, 24> = "    ".chars().chain(text.chars()).map(segments).collect();
+            for window in codes.windows(4) {
+                let mut digits = [0u8; 8];
+                digits[..4].copy_from_slice(window);
+                if display.write_raw(0, &digits).is_err() {
+                    return;
+                }
+                cortex_m::asm::delay(SCROLL_STEP_CYCLES * 8);
+            }
+        }
+    }
+
+    // Segments of a digit without the Code B decoder, bits DP A B C D E F G
+    #[cfg(feature = "display-max7219-7seg")]
+    fn segments(c: char) -> u8 {
+        const DIGITS: [u8; 10] = [0x7E, 0x30, 0x6D, 0x79, 0x33, 0x5B, 0x5F, 0x70, 0x7F, 0x7B];
+        match c {
+            '0'..='9' => DIGITS[c as usize - '0' as usize],
+            'P' => 0x67,
+            'A' => 0x77,
+            'N' => 0x15,
+            'I' => 0x30,
+            'C' => 0x4E,
+            'L' => 0x0E,
+            _ => 0x00,
+        }
+    }
+}
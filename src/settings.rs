@@ -0,0 +1,525 @@
+use core::cell::{Cell, RefCell};
+
+use defmt::{info, warn};
+use ds1307::{Datelike, NaiveDateTime, Timelike};
+use embassy_stm32::flash::{Blocking, Flash, FLASH_SIZE};
+use embassy_sync::blocking_mutex::raw::{CriticalSectionRawMutex, ThreadModeRawMutex};
+use embassy_sync::blocking_mutex::Mutex;
+
+use crate::error::ClockError;
+
+// Last 1 KiB page of the flash, memory.x keeps the firmware below it
+const PAGE_SIZE: u32 = 1024;
+const SETTINGS_OFFSET: u32 = FLASH_SIZE as u32 - PAGE_SIZE;
+// memory.x ends the firmware one page before the end of a 64 KiB part
+const _: () = assert!(FLASH_SIZE == 64 * 1024, "memory.x has to leave out the settings page");
+
+const SETTINGS_MAGIC: u16 = 0x5343;
+// Magic, payload length and CRC
+const HEADER_LEN: usize = 4;
+const CRC_LEN: usize = 2;
+const STORAGE_LEN: usize = 128;
+
+pub const BRIGHTNESS_BANDS: usize = 4;
+pub const DEFAULT_LEVEL: u8 = 3;
+pub const MAX_LEVEL: u8 = 15;
+pub const MAX_WAKE_SECONDS: u8 = 60;
+pub const ROTATION_SLOTS: usize = 4;
+pub const MAX_DWELL_SECONDS: u8 = 120;
+pub const MIN_MENU_TIMEOUT: u8 = 10;
+pub const MAX_MENU_TIMEOUT: u8 = 240;
+// 12-bit ADC
+pub const ADC_MAX: u16 = 4095;
+
+/// From `start_hour:start_minute` until the next band starts, the display runs at `level`.
+/// Bands without a level are skipped.
+#[derive(Clone, Copy, PartialEq)]
+pub struct BrightnessBand {
+    pub start_hour: u8,
+    pub start_minute: u8,
+    pub level: Option<u8>,
+}
+
+impl BrightnessBand {
+    const fn new(start_hour: u8, start_minute: u8, level: Option<u8>) -> Self {
+        BrightnessBand { start_hour, start_minute, level }
+    }
+
+    fn start(&self) -> u16 {
+        self.start_hour as u16 * 60 + self.start_minute as u16
+    }
+}
+
+/// Brightness over the day. Weekdays set in `alternate_days` (bit 0 is Monday) use
+/// the alternate bands instead of the regular ones.
+#[derive(Clone, Copy, PartialEq)]
+pub struct BrightnessSchedule {
+    pub bands: [BrightnessBand; BRIGHTNESS_BANDS],
+    pub alternate: [BrightnessBand; BRIGHTNESS_BANDS],
+    pub alternate_days: u8,
+}
+
+impl BrightnessSchedule {
+    /// Level of the latest band started before `datetime`, a day before the first band
+    /// still runs at the last band of the table.
+    pub fn level_at(&self, datetime: &NaiveDateTime) -> u8 {
+        let weekday = datetime.weekday().num_days_from_monday();
+        let bands = if self.alternate_days & (1 << weekday) != 0 { &self.alternate } else { &self.bands };
+        let now = (datetime.hour() * 60 + datetime.minute()) as u16;
+
+        let enabled = || bands.iter().filter(|band| band.level.is_some());
+        let current = enabled()
+            .filter(|band| band.start() <= now)
+            .max_by_key(|band| band.start())
+            .or_else(|| enabled().max_by_key(|band| band.start()));
+
+        current.and_then(|band| band.level).unwrap_or(DEFAULT_LEVEL)
+    }
+}
+
+// Position in the list is the menu choice index and the stored value, so new
+// variants go at the end. Leaving a variant out of the list does not compile
+macro_rules! indexed {
+    ($name:ident, [$($variant:ident),+ $(,)?]) => {
+        impl $name {
+            const ALL: &'static [$name] = &[$($name::$variant),+];
+
+            pub fn index(&self) -> usize {
+                $name::ALL.iter().position(|value| value == self).unwrap_or(0)
+            }
+
+            pub fn from_index(index: usize) -> Self {
+                $name::ALL.get(index).copied().unwrap_or($name::ALL[0])
+            }
+        }
+
+        const _: fn($name) = |value| match value {
+            $($name::$variant => ()),+
+        };
+    };
+}
+
+/// Where the display brightness comes from.
+#[derive(Clone, Copy, PartialEq)]
+pub enum BrightnessMode {
+    Schedule,
+    Sensor,
+}
+
+indexed!(BrightnessMode, [Schedule, Sensor]);
+
+/// Light sensor calibration, readings between the two points map linearly to levels
+/// between their levels. Readings outside stay at the nearer point's level.
+#[derive(Clone, Copy, PartialEq)]
+pub struct LightCurve {
+    pub dark_reading: u16,
+    pub dark_level: u8,
+    pub bright_reading: u16,
+    pub bright_level: u8,
+}
+
+impl LightCurve {
+    pub fn level(&self, reading: u16) -> u8 {
+        let (dark, bright) = (self.dark_reading as i32, self.bright_reading as i32);
+        let (low, high) = (self.dark_level as i32, self.bright_level as i32);
+        if dark == bright {
+            return self.bright_level;
+        }
+
+        // Works for sensors wired either way round, bright may read lower than dark
+        let position = ((reading as i32 - dark) * 256 / (bright - dark)).clamp(0, 256);
+        (low + ((high - low) * position + 128) / 256) as u8
+    }
+}
+
+/// What the clock face turns into during the night window.
+#[derive(Clone, Copy, PartialEq)]
+pub enum NightIdle {
+    /// Display shut down
+    Off,
+    /// Single dot in the corner at the lowest intensity
+    Dot,
+}
+
+indexed!(NightIdle, [Off, Dot]);
+
+/// Night window from start to end, wrapping over midnight when end is earlier.
+/// A button press shows the time for `wake_seconds`, 0 keeps the display dark
+/// until an alarm goes off.
+#[derive(Clone, Copy, PartialEq)]
+pub struct NightMode {
+    pub enabled: bool,
+    pub start_hour: u8,
+    pub start_minute: u8,
+    pub end_hour: u8,
+    pub end_minute: u8,
+    pub idle: NightIdle,
+    pub wake_seconds: u8,
+}
+
+impl NightMode {
+    pub fn contains(&self, datetime: &NaiveDateTime) -> bool {
+        let start = self.start_hour as u32 * 60 + self.start_minute as u32;
+        let end = self.end_hour as u32 * 60 + self.end_minute as u32;
+        let now = datetime.hour() * 60 + datetime.minute();
+
+        self.enabled && if start <= end { start <= now && now < end } else { now >= start || now < end }
+    }
+}
+
+/// How the clock face and the time editors show hours.
+#[derive(Clone, Copy, PartialEq)]
+pub enum HourFormat {
+    H24,
+    /// 1 to 12 with a blank leading zero, afternoon marked by a pixel under the hour
+    H12,
+}
+
+indexed!(HourFormat, [H24, H12]);
+
+/// How the date face shows the date.
+#[derive(Clone, Copy, PartialEq)]
+pub enum DateFormat {
+    /// DD.MM
+    DayMonth,
+    /// MM/DD
+    MonthDay,
+    /// YYYY-MM-DD scrolling
+    Iso,
+    /// Weekday, day, month name and year scrolling
+    Long,
+}
+
+indexed!(DateFormat, [DayMonth, MonthDay, Iso, Long]);
+
+/// How the time face looks.
+#[derive(Clone, Copy, PartialEq)]
+pub enum TimeFace {
+    Digits,
+    /// BCD columns for hours, minutes and seconds
+    Binary,
+    /// Share of the day gone by
+    DayBar,
+    /// Roman numerals scrolling
+    Roman,
+    /// Time in words to five minutes, scrolling
+    Words,
+}
+
+indexed!(TimeFace, [Digits, Binary, DayBar, Roman, Words]);
+
+/// Language of words shown on the display.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Language {
+    Polish,
+    English,
+}
+
+indexed!(Language, [Polish, English]);
+
+/// Clock faces the rotation can show.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Face {
+    Time,
+    Date,
+    Year,
+    Weekday,
+    Temperature,
+}
+
+indexed!(Face, [Time, Date, Year, Weekday, Temperature]);
+
+/// One step of the face rotation, skipped when `dwell_seconds` is 0.
+#[derive(Clone, Copy, PartialEq)]
+pub struct RotationSlot {
+    pub face: Face,
+    pub dwell_seconds: u8,
+}
+
+/// Faces the idle clock cycles through in slot order.
+#[derive(Clone, Copy, PartialEq)]
+pub struct FaceRotation {
+    pub enabled: bool,
+    pub slots: [RotationSlot; ROTATION_SLOTS],
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Settings {
+    pub brightness: BrightnessSchedule,
+    pub brightness_mode: BrightnessMode,
+    pub light: LightCurve,
+    pub night: NightMode,
+    /// Seconds without a press before menus and editors give up and return to the clock
+    pub menu_timeout: u8,
+    pub hour_format: HourFormat,
+    pub date_format: DateFormat,
+    pub rotation: FaceRotation,
+    pub time_face: TimeFace,
+    pub language: Language,
+}
+
+impl Settings {
+    // Same levels as the old fixed night dimming, 23:00 to 06:00
+    const DEFAULT: Settings = Settings {
+        brightness: BrightnessSchedule {
+            bands: [
+                BrightnessBand::new(6, 0, Some(DEFAULT_LEVEL)),
+                BrightnessBand::new(23, 0, Some(0)),
+                BrightnessBand::new(0, 0, None),
+                BrightnessBand::new(0, 0, None),
+            ],
+            alternate: [
+                BrightnessBand::new(8, 0, Some(DEFAULT_LEVEL)),
+                BrightnessBand::new(23, 0, Some(0)),
+                BrightnessBand::new(0, 0, None),
+                BrightnessBand::new(0, 0, None),
+            ],
+            alternate_days: 0,
+        },
+        brightness_mode: BrightnessMode::Schedule,
+        light: LightCurve {
+            dark_reading: 200,
+            dark_level: 0,
+            bright_reading: 3000,
+            bright_level: 8,
+        },
+        night: NightMode {
+            enabled: false,
+            start_hour: 23,
+            start_minute: 0,
+            end_hour: 6,
+            end_minute: 0,
+            idle: NightIdle::Off,
+            wake_seconds: 5,
+        },
+        menu_timeout: 30,
+        hour_format: HourFormat::H24,
+        date_format: DateFormat::DayMonth,
+        rotation: FaceRotation {
+            enabled: false,
+            slots: [
+                RotationSlot { face: Face::Time, dwell_seconds: 50 },
+                RotationSlot { face: Face::Date, dwell_seconds: 5 },
+                RotationSlot { face: Face::Weekday, dwell_seconds: 0 },
+                RotationSlot { face: Face::Year, dwell_seconds: 0 },
+            ],
+        },
+        time_face: TimeFace::Digits,
+        language: Language::English,
+    };
+
+    // New fields go to the end, older records are read with defaults for them
+    fn encode(&self, writer: &mut Writer) {
+        for band in self.brightness.bands.iter().chain(self.brightness.alternate.iter()) {
+            writer.u8(band.start_hour);
+            writer.u8(band.start_minute);
+            writer.u8(band.level.unwrap_or(u8::MAX));
+        }
+        writer.u8(self.brightness.alternate_days);
+
+        writer.u8(self.brightness_mode.index() as u8);
+        writer.u16(self.light.dark_reading);
+        writer.u8(self.light.dark_level);
+        writer.u16(self.light.bright_reading);
+        writer.u8(self.light.bright_level);
+
+        writer.u8(self.night.enabled as u8);
+        writer.u8(self.night.start_hour);
+        writer.u8(self.night.start_minute);
+        writer.u8(self.night.end_hour);
+        writer.u8(self.night.end_minute);
+        writer.u8(self.night.idle.index() as u8);
+        writer.u8(self.night.wake_seconds);
+
+        writer.u8(self.menu_timeout);
+        writer.u8(self.hour_format.index() as u8);
+        writer.u8(self.date_format.index() as u8);
+
+        writer.u8(self.rotation.enabled as u8);
+        for slot in self.rotation.slots.iter() {
+            writer.u8(slot.face.index() as u8);
+            writer.u8(slot.dwell_seconds);
+        }
+
+        writer.u8(self.time_face.index() as u8);
+        writer.u8(self.language.index() as u8);
+    }
+
+    fn decode(reader: &mut Reader) -> Self {
+        let mut settings = Settings::DEFAULT;
+
+        let schedule = &mut settings.brightness;
+        for band in schedule.bands.iter_mut().chain(schedule.alternate.iter_mut()) {
+            band.start_hour = reader.u8(band.start_hour).min(23);
+            band.start_minute = reader.u8(band.start_minute).min(59);
+            band.level = match reader.u8(band.level.unwrap_or(u8::MAX)) {
+                level if level <= MAX_LEVEL => Some(level),
+                _ => None,
+            };
+        }
+        schedule.alternate_days = reader.u8(schedule.alternate_days) & 0x7F;
+
+        settings.brightness_mode =
+            BrightnessMode::from_index(reader.u8(settings.brightness_mode.index() as u8) as usize);
+        let light = &mut settings.light;
+        light.dark_reading = reader.u16(light.dark_reading).min(ADC_MAX);
+        light.dark_level = reader.u8(light.dark_level).min(MAX_LEVEL);
+        light.bright_reading = reader.u16(light.bright_reading).min(ADC_MAX);
+        light.bright_level = reader.u8(light.bright_level).min(MAX_LEVEL);
+
+        let night = &mut settings.night;
+        night.enabled = reader.u8(night.enabled as u8) == 1;
+        night.start_hour = reader.u8(night.start_hour).min(23);
+        night.start_minute = reader.u8(night.start_minute).min(59);
+        night.end_hour = reader.u8(night.end_hour).min(23);
+        night.end_minute = reader.u8(night.end_minute).min(59);
+        night.idle = NightIdle::from_index(reader.u8(night.idle.index() as u8) as usize);
+        night.wake_seconds = reader.u8(night.wake_seconds).min(MAX_WAKE_SECONDS);
+
+        settings.menu_timeout = reader.u8(settings.menu_timeout).clamp(MIN_MENU_TIMEOUT, MAX_MENU_TIMEOUT);
+        settings.hour_format = HourFormat::from_index(reader.u8(settings.hour_format.index() as u8) as usize);
+        settings.date_format = DateFormat::from_index(reader.u8(settings.date_format.index() as u8) as usize);
+
+        let rotation = &mut settings.rotation;
+        rotation.enabled = reader.u8(rotation.enabled as u8) == 1;
+        for slot in rotation.slots.iter_mut() {
+            slot.face = Face::from_index(reader.u8(slot.face.index() as u8) as usize);
+            slot.dwell_seconds = reader.u8(slot.dwell_seconds).min(MAX_DWELL_SECONDS);
+        }
+
+        settings.time_face = TimeFace::from_index(reader.u8(settings.time_face.index() as u8) as usize);
+        settings.language = Language::from_index(reader.u8(settings.language.index() as u8) as usize);
+
+        settings
+    }
+}
+
+struct Writer {
+    buffer: [u8; STORAGE_LEN],
+    len: usize,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.buffer[HEADER_LEN + self.len] = value;
+        self.len += 1;
+    }
+
+    fn u16(&mut self, value: u16) {
+        value.to_le_bytes().into_iter().for_each(|byte| self.u8(byte));
+    }
+}
+
+struct Reader<'a> {
+    payload: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn u8(&mut self, default: u8) -> u8 {
+        let value = self.payload.get(self.position).copied().unwrap_or(default);
+        self.position += 1;
+        value
+    }
+
+    fn u16(&mut self, default: u16) -> u16 {
+        let [low, high] = default.to_le_bytes();
+        u16::from_le_bytes([self.u8(low), self.u8(high)])
+    }
+}
+
+static SETTINGS: Mutex<CriticalSectionRawMutex, Cell<Settings>> = Mutex::new(Cell::new(Settings::DEFAULT));
+// Settings are written only from thread-mode tasks, never from interrupts, so a
+// thread-mode lock is enough. It keeps the erase out of a critical section, the CPU
+// still stalls on fetches from the bank while a page erase runs.
+static FLASH: Mutex<ThreadModeRawMutex, RefCell<Option<Flash<'static, Blocking>>>> = Mutex::new(RefCell::new(None));
+
+/// Loads the settings page. A blank page keeps the defaults, a corrupted one
+/// keeps them too and reports `SettingsCrc`.
+pub fn init(mut flash: Flash<'static, Blocking>) -> Result<(), ClockError> {
+    let mut buffer = [0; STORAGE_LEN];
+    let read = flash.blocking_read(SETTINGS_OFFSET, &mut buffer);
+
+    let loaded = read.map_err(ClockError::from).and_then(|_| load(&buffer));
+    FLASH.lock(|stored| *stored.borrow_mut() = Some(flash));
+    if let Ok(Some(settings)) = loaded {
+        SETTINGS.lock(|stored| stored.set(settings));
+    }
+
+    match loaded {
+        Ok(Some(_)) => info! {"Settings loaded"},
+        Ok(None) => info! {"No settings stored, using defaults"},
+        Err(_) => warn! {"Settings unreadable, using defaults"},
+    }
+    loaded.map(|_| ())
+}
+
+fn load(buffer: &[u8; STORAGE_LEN]) -> Result<Option<Settings>, ClockError> {
+    let magic = u16::from_le_bytes([buffer[0], buffer[1]]);
+    if magic != SETTINGS_MAGIC {
+        return Ok(None);
+    }
+
+    let len = u16::from_le_bytes([buffer[2], buffer[3]]) as usize;
+    if HEADER_LEN + len + CRC_LEN > STORAGE_LEN {
+        return Err(ClockError::SettingsCrc);
+    }
+
+    let stored_crc = u16::from_le_bytes([buffer[HEADER_LEN + len], buffer[HEADER_LEN + len + 1]]);
+    if crc16(&buffer[..HEADER_LEN + len]) != stored_crc {
+        return Err(ClockError::SettingsCrc);
+    }
+
+    let mut reader = Reader { payload: &buffer[HEADER_LEN..HEADER_LEN + len], position: 0 };
+    Ok(Some(Settings::decode(&mut reader)))
+}
+
+pub fn get() -> Settings {
+    SETTINGS.lock(|settings| settings.get())
+}
+
+/// Changes the settings in RAM and writes them to flash when anything changed.
+pub fn update(change: impl FnOnce(&mut Settings)) -> Result<(), ClockError> {
+    let changed = SETTINGS.lock(|stored| {
+        let mut settings = stored.get();
+        change(&mut settings);
+
+        let changed = settings != stored.get();
+        stored.set(settings);
+        changed.then_some(settings)
+    });
+
+    let Some(settings) = changed else { return Ok(()) };
+    FLASH.lock(|flash| match flash.borrow_mut().as_mut() {
+        Some(flash) => save(flash, &settings),
+        None => Ok(()),
+    })
+}
+
+fn save(flash: &mut Flash<'static, Blocking>, settings: &Settings) -> Result<(), ClockError> {
+    let mut writer = Writer { buffer: [0xFF; STORAGE_LEN], len: 0 };
+    settings.encode(&mut writer);
+
+    let len = writer.len;
+    writer.buffer[..2].copy_from_slice(&SETTINGS_MAGIC.to_le_bytes());
+    writer.buffer[2..4].copy_from_slice(&(len as u16).to_le_bytes());
+    let crc = crc16(&writer.buffer[..HEADER_LEN + len]);
+    writer.buffer[HEADER_LEN + len..HEADER_LEN + len + CRC_LEN].copy_from_slice(&crc.to_le_bytes());
+
+    flash.blocking_erase(SETTINGS_OFFSET, SETTINGS_OFFSET + PAGE_SIZE)?;
+    flash.blocking_write(SETTINGS_OFFSET, &writer.buffer)?;
+    info! {"Settings saved"};
+    Ok(())
+}
+
+// CRC-16/CCITT-FALSE
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
@@ -1,16 +1,34 @@
 use defmt::info;
-use embassy_stm32::gpio::Output;
 use embassy_stm32::time::Hertz;
 
-use embassy_time::Timer;
+use embassy_time::{Duration, Instant, Timer};
 
 use embassy_stm32::timer::Channel;
 
-use embassy_stm32::peripherals::{PA5, PA7, PB0, TIM1};
+use embassy_stm32::peripherals::TIM1;
 
 use embassy_stm32::timer::simple_pwm::SimplePwm;
-use max7219::connectors::PinConnector;
-use max7219::MAX7219;
+use super::{Display, DisplayBackend};
+
+use crate::error::ClockError;
+use crate::locale::Text;
+use super::animation::{Easing, Frame, Keyframe, Player, Timeline, FRAME_INTERVAL};
+use super::matrix_display::MatrixDisplay;
+
+// Rings for this long unless a button stops it
+const ALARM_DURATION: Duration = Duration::from_secs(10 * 60);
+const BEEP_MS: u64 = 100;
+// Beep and the gap after it
+const BEEP_STEP_MS: u64 = 150;
+const BEEP_CYCLE_MS: u64 = 600;
+
+/// "ALARM" swaying left and right while it rings.
+static ALARM_ANIMATION: &[Timeline] = &[
+    Timeline::new(&[
+        Keyframe::new(0, Frame::Scroll { text: Text::Alarm, offset: -4 }).eased(Easing::EaseInOut),
+        Keyframe::new(600, Frame::Scroll { text: Text::Alarm, offset: 20 }),
+    ], 600).ping_pong(0),
+];
 
 pub struct Alarm<'a> {
     hour: u32,
@@ -63,50 +81,32 @@ impl<'a> Alarm<'a> {
         }
     }
 
-    pub async fn play_alarm<'b>(&mut self, buttons: &super::buttons::Buttons<'b>, display: &mut MAX7219<PinConnector<Output<'b, PA7>, Output<'b, PB0>, Output<'b, PA5>>>) {
+    /// Beeps and plays the alarm animation until a button is pressed or the alarm
+    /// gives up.
+    pub async fn play_alarm<'b>(&mut self, buttons: &super::buttons::Buttons<'b>, display: &mut Display<'b>) -> Result<(), ClockError> {
         let sound = super::symbols::FREQUENCIES[21].1;
-        let buzz_length = 100;
+        self.pwm.set_frequency(sound);
 
         info! {"Alarm!!!"};
-        let mut ticks = 0;
-        loop {
-
-            for _ in 0..3 {
-                if self.check_off_and_play(buttons, sound, buzz_length, display).await {
-                    display.power_on().unwrap();
-
-                    return;
-                }
-            }
-            for _ in 0..3 {
-                Timer::after_millis(50).await;
-                if buttons.any_pin_is_low().await {
-                    display.power_on().unwrap();
-
-                    return;
-                }
-            } 
-
-            ticks += 1;
-            if ticks == 1000 {
-                break;
+        let mut matrices = MatrixDisplay::new();
+        let mut player = Player::new(ALARM_ANIMATION);
+        let started = Instant::now();
+        while started.elapsed() < ALARM_DURATION && !buttons.any_pin_is_low().await {
+            // Three beeps, then a pause as long as one beep and gap
+            let cycle = started.elapsed().as_millis() % BEEP_CYCLE_MS;
+            if cycle < 3 * BEEP_STEP_MS && cycle % BEEP_STEP_MS < BEEP_MS {
+                self.pwm.enable(Channel::Ch2);
+            } else {
+                self.pwm.disable(Channel::Ch2);
             }
+
+            player.render(&mut matrices);
+            matrices.display_update(display).await;
+            Timer::after(FRAME_INTERVAL).await;
         }
-    }
 
-    pub async fn check_off_and_play<'b>(
-        &mut self,
-        buttons: &super::buttons::Buttons<'b>,
-        sound: Hertz,
-        buzz_length: u64,
-        display: &mut MAX7219<PinConnector<Output<'b, PA7>, Output<'b, PB0>, Output<'b, PA5>>>
-    ) -> bool {
-        display.power_on().unwrap();
-        self.play_sound(sound, buzz_length).await;
-        display.power_off().unwrap();
-
-        Timer::after_millis(50).await;
-        buttons.any_pin_is_low().await
+        self.pwm.disable(Channel::Ch2);
+        display.power_on().await
     }
 
     pub async fn play_sound(&mut self, sound: Hertz, buzz_length: u64) {
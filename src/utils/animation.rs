@@ -0,0 +1,236 @@
+use embassy_time::{Duration, Instant, Timer};
+
+use crate::locale::{self, Text};
+use super::matrix_display::MatrixDisplay;
+use super::Display;
+
+/// Time between drawn frames while playing.
+pub const FRAME_INTERVAL: Duration = Duration::from_millis(20);
+// Fixed point one for easing curves
+const ONE: u32 = 1024;
+const SLIDE_TIME: Duration = Duration::from_millis(400);
+
+/// What a keyframe puts on the display.
+#[derive(Clone, Copy)]
+pub enum Frame {
+    /// Four matrices as they are
+    Bitmap([[u8; 8]; 4]),
+    /// First four characters of the text in the language from the settings
+    Text(Text),
+    /// Text moved left by `offset` columns, or right when negative. The offset is
+    /// eased towards the next keyframe when that scrolls too
+    Scroll { text: Text, offset: i16 },
+}
+
+impl Frame {
+    fn draw(&self, matrices: &mut MatrixDisplay) {
+        match self {
+            Frame::Bitmap(frame) => matrices.set_frame(*frame),
+            Frame::Text(text) => matrices.set_text(locale::text(*text)),
+            Frame::Scroll { text, offset } => matrices.set_scrolled_text(locale::text(*text), *offset as isize),
+        }
+    }
+}
+
+/// How a keyframe moves towards the next one.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Easing {
+    /// Stays as it is until the next keyframe
+    Hold,
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    // Progress `t` from 0 to ONE mapped along the curve
+    fn apply(self, t: u32) -> u32 {
+        let t = t.min(ONE);
+        match self {
+            Easing::Hold => 0,
+            Easing::Linear => t,
+            Easing::EaseIn => t * t / ONE,
+            Easing::EaseOut => ONE - (ONE - t) * (ONE - t) / ONE,
+            Easing::EaseInOut if t < ONE / 2 => 2 * t * t / ONE,
+            Easing::EaseInOut => ONE - 2 * (ONE - t) * (ONE - t) / ONE,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Keyframe {
+    /// Milliseconds from the start of the timeline
+    pub at: u16,
+    pub frame: Frame,
+    pub easing: Easing,
+}
+
+impl Keyframe {
+    pub const fn new(at: u16, frame: Frame) -> Self {
+        Keyframe { at, frame, easing: Easing::Hold }
+    }
+
+    pub const fn eased(self, easing: Easing) -> Self {
+        Keyframe { easing, ..self }
+    }
+}
+
+/// How often a timeline plays, a count of 0 repeats forever.
+#[derive(Clone, Copy)]
+pub enum Playback {
+    Once,
+    Loop(u8),
+    /// Forwards then backwards, the count is of round trips
+    PingPong(u8),
+}
+
+/// Keyframes sorted by time over `length` milliseconds. Plain data, so animations
+/// can be declared as constants and stay in flash.
+#[derive(Clone, Copy)]
+pub struct Timeline {
+    pub keyframes: &'static [Keyframe],
+    pub length: u16,
+    pub playback: Playback,
+}
+
+impl Timeline {
+    pub const fn new(keyframes: &'static [Keyframe], length: u16) -> Self {
+        Timeline { keyframes, length, playback: Playback::Once }
+    }
+
+    pub const fn looped(self, times: u8) -> Self {
+        Timeline { playback: Playback::Loop(times), ..self }
+    }
+
+    pub const fn ping_pong(self, times: u8) -> Self {
+        Timeline { playback: Playback::PingPong(times), ..self }
+    }
+
+    // Passes through the keyframes, `None` when it never ends
+    fn passes(&self) -> Option<u32> {
+        match self.playback {
+            Playback::Once => Some(1),
+            Playback::Loop(0) | Playback::PingPong(0) => None,
+            Playback::Loop(times) => Some(times as u32),
+            Playback::PingPong(times) => Some(times as u32 * 2),
+        }
+    }
+
+    /// Total play time, `None` for endless playback.
+    pub fn duration(&self) -> Option<Duration> {
+        self.passes().map(|passes| Duration::from_millis(passes as u64 * self.length as u64))
+    }
+
+    /// Draws the frame `elapsed` after the start, false once the timeline has ended.
+    pub fn render(&self, elapsed: Duration, matrices: &mut MatrixDisplay) -> bool {
+        let length = self.length.max(1) as u64;
+        let elapsed = elapsed.as_millis();
+        let pass = elapsed / length;
+        if self.passes().is_some_and(|passes| pass >= passes as u64) || self.keyframes.is_empty() {
+            return false;
+        }
+
+        let local = (elapsed % length) as u32;
+        let position = match self.playback {
+            Playback::PingPong(_) if pass % 2 == 1 => length as u32 - local,
+            _ => local,
+        };
+        self.draw(position, matrices);
+        true
+    }
+
+    fn draw(&self, position: u32, matrices: &mut MatrixDisplay) {
+        let index = self.keyframes.iter().rposition(|keyframe| keyframe.at as u32 <= position).unwrap_or(0);
+        let current = &self.keyframes[index];
+
+        let Some(next) = self.keyframes.get(index + 1) else {
+            current.frame.draw(matrices);
+            return;
+        };
+
+        match (current.frame, next.frame) {
+            (Frame::Scroll { text, offset: from }, Frame::Scroll { offset: to, .. }) if current.easing != Easing::Hold => {
+                let span = (next.at - current.at).max(1) as u32;
+                let progress = current.easing.apply(position.saturating_sub(current.at as u32) * ONE / span) as i32;
+                let offset = from as i32 + (to as i32 - from as i32) * progress / ONE as i32;
+                matrices.set_scrolled_text(locale::text(text), offset as isize);
+            }
+            (frame, _) => frame.draw(matrices),
+        }
+    }
+}
+
+/// Plays timelines one after another, each starting when the previous one ends.
+pub struct Player {
+    sequence: &'static [Timeline],
+    index: usize,
+    started: Instant,
+}
+
+impl Player {
+    pub fn new(sequence: &'static [Timeline]) -> Self {
+        Player { sequence, index: 0, started: Instant::now() }
+    }
+
+    /// Draws the current frame, false once the whole sequence has played.
+    pub fn render(&mut self, matrices: &mut MatrixDisplay) -> bool {
+        while let Some(timeline) = self.sequence.get(self.index) {
+            if timeline.render(self.started.elapsed(), matrices) {
+                return true;
+            }
+            // Only finite timelines end
+            self.started += timeline.duration().unwrap_or(Duration::from_ticks(0));
+            self.index += 1;
+        }
+        false
+    }
+}
+
+/// Plays a sequence to its end. Endless timelines never return, loop over
+/// `Player::render` instead when something else has to run meanwhile.
+pub async fn play(sequence: &'static [Timeline], matrices: &mut MatrixDisplay, display: &mut Display<'_>) {
+    let mut player = Player::new(sequence);
+    while player.render(matrices) {
+        matrices.display_update(display).await;
+        Timer::after(FRAME_INTERVAL).await;
+    }
+}
+
+/// Newly drawn content pushing the previous frame out to the left.
+pub struct Slide {
+    from: [[u8; 8]; 4],
+    started: Instant,
+}
+
+impl Slide {
+    pub fn new(from: [[u8; 8]; 4]) -> Self {
+        Slide { from, started: Instant::now() }
+    }
+
+    /// Mixes the old frame into the new one already in `matrices`, false once the
+    /// slide is over and the new frame is left as it is.
+    pub fn render(&self, matrices: &mut MatrixDisplay) -> bool {
+        let elapsed = self.started.elapsed();
+        if elapsed >= SLIDE_TIME {
+            return false;
+        }
+
+        let progress = (elapsed.as_millis() * ONE as u64 / SLIDE_TIME.as_millis()) as u32;
+        let shift = (Easing::EaseInOut.apply(progress) * 32 / ONE) as usize;
+        let to = matrices.frame();
+
+        let mut frame = [[0u8; 8]; 4];
+        for column in 0..32 {
+            let source = column + shift;
+            let (strip, source) = if source < 32 { (&self.from, source) } else { (&to, source - 32) };
+            for row in 0..8 {
+                if strip[source / 8][row] & (0x80 >> (source % 8)) != 0 {
+                    frame[column / 8][row] |= 0x80 >> (column % 8);
+                }
+            }
+        }
+        Frame::Bitmap(frame).draw(matrices);
+        true
+    }
+}
@@ -0,0 +1,119 @@
+use defmt::info;
+use ds1307::{Datelike, NaiveDate, NaiveDateTime, Timelike};
+use embassy_stm32::pac;
+use embassy_stm32::pac::rcc::vals::Rtcsel;
+
+// Counter value 0 is 2000-01-01 00:00:00, the same epoch as DS1307
+const EPOCH_DAYS_FROM_CE: i32 = 730_120;
+const SECONDS_IN_DAY: u32 = 86_400;
+const LSE_STARTUP_LOOPS: u32 = 1_000_000;
+
+/// STM32F103 backup-domain RTC, a plain 32-bit seconds counter clocked from LSE.
+pub struct BackupRtc {
+    running: bool,
+}
+
+impl BackupRtc {
+    pub fn init() -> Self {
+        pac::RCC.apb1enr().modify(|w| {
+            w.set_pwren(true);
+            w.set_bkpen(true);
+        });
+        pac::PWR.cr().modify(|w| w.set_dbp(true));
+
+        // Backup domain keeps running through a reset, configure it only once
+        if !pac::RCC.bdcr().read().rtcen() {
+            pac::RCC.bdcr().modify(|w| w.set_lseon(true));
+
+            let mut loops = 0;
+            while !pac::RCC.bdcr().read().lserdy() {
+                loops += 1;
+                if loops == LSE_STARTUP_LOOPS {
+                    info! {"LSE did not start, internal RTC disabled"};
+                    return BackupRtc { running: false };
+                }
+            }
+
+            pac::RCC.bdcr().modify(|w| {
+                w.set_rtcsel(Rtcsel::LSE);
+                w.set_rtcen(true);
+            });
+
+            Self::config_mode(|| {
+                pac::RTC.prlh().write(|w| w.set_prlh(0));
+                pac::RTC.prll().write(|w| w.set_prll(32_767));
+            });
+        }
+
+        // Registers are valid only after the first synchronisation with the RTC clock
+        pac::RTC.crl().modify(|w| w.set_rsf(false));
+        // Never sets when LSE stopped after the domain was configured
+        let mut loops = 0;
+        while !pac::RTC.crl().read().rsf() {
+            loops += 1;
+            if loops == LSE_STARTUP_LOOPS {
+                info! {"RTC did not synchronise, internal RTC disabled"};
+                return BackupRtc { running: false };
+            }
+        }
+
+        BackupRtc { running: true }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn datetime(&self) -> Option<NaiveDateTime> {
+        if !self.running {
+            return None;
+        }
+
+        from_counter(Self::counter())
+    }
+
+    pub fn set_datetime(&mut self, datetime: &NaiveDateTime) {
+        if !self.running {
+            return;
+        }
+
+        let counter = to_counter(datetime);
+        Self::config_mode(|| {
+            pac::RTC.cnth().write(|w| w.set_cnth((counter >> 16) as u16));
+            pac::RTC.cntl().write(|w| w.set_cntl(counter as u16));
+        });
+    }
+
+    /// Raw seconds since 2000-01-01, usable without an instance, e.g. from the panic handler.
+    pub fn counter() -> u32 {
+        // Low half can overflow between the two reads, retry until high half is stable
+        loop {
+            let high = pac::RTC.cnth().read().cnth();
+            let low = pac::RTC.cntl().read().cntl();
+            if high == pac::RTC.cnth().read().cnth() {
+                return (high as u32) << 16 | low as u32;
+            }
+        }
+    }
+
+    fn config_mode(f: impl FnOnce()) {
+        while !pac::RTC.crl().read().rtoff() {}
+        pac::RTC.crl().modify(|w| w.set_cnf(true));
+        f();
+        pac::RTC.crl().modify(|w| w.set_cnf(false));
+        while !pac::RTC.crl().read().rtoff() {}
+    }
+}
+
+fn to_counter(datetime: &NaiveDateTime) -> u32 {
+    let days = (datetime.date().num_days_from_ce() - EPOCH_DAYS_FROM_CE).max(0) as u32;
+    days * SECONDS_IN_DAY + datetime.num_seconds_from_midnight()
+}
+
+pub fn from_counter(counter: u32) -> Option<NaiveDateTime> {
+    let days = EPOCH_DAYS_FROM_CE + (counter / SECONDS_IN_DAY) as i32;
+    let seconds = counter % SECONDS_IN_DAY;
+
+    NaiveDate::from_num_days_from_ce_opt(days)?
+        .and_hms_opt(seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
@@ -0,0 +1,65 @@
+use core::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+
+use defmt::info;
+use ds1307::NaiveDateTime;
+use embassy_time::Instant;
+
+use crate::error::ClockError;
+use crate::settings::{self, BrightnessMode, DEFAULT_LEVEL, MAX_LEVEL};
+use super::{set_display_intensity, Display};
+
+// One intensity step per period, a full 0 to 15 fade takes about three seconds
+const FADE_STEP_MS: u32 = 200;
+
+// Backends start dark, the first updates fade in to the target
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+static TARGET: AtomicU8 = AtomicU8::new(DEFAULT_LEVEL);
+static LIMIT: AtomicU8 = AtomicU8::new(MAX_LEVEL);
+static LAST_STEP: AtomicU32 = AtomicU32::new(0);
+
+fn now_ms() -> u32 {
+    Instant::now().as_millis() as u32
+}
+
+/// Level the display fades to.
+pub fn set_target(level: u8) {
+    let level = level.min(MAX_LEVEL);
+    if TARGET.swap(level, Ordering::Relaxed) != level {
+        info! {"Brightness target {}", level};
+    }
+}
+
+/// Caps the level whatever the schedule or sensor ask for, e.g. during the night.
+pub fn set_limit(level: u8) {
+    LIMIT.store(level.min(MAX_LEVEL), Ordering::Relaxed);
+}
+
+/// Takes the target from the stored brightness schedule, unless the light sensor
+/// drives the brightness.
+pub fn follow_schedule(datetime: &NaiveDateTime) {
+    let settings = settings::get();
+    if settings.brightness_mode == BrightnessMode::Schedule {
+        set_target(settings.brightness.level_at(datetime));
+    }
+}
+
+/// Moves the display intensity one step towards the target once per fade period.
+/// Every frame goes through here, so all screens share the same brightness.
+pub async fn fade(display: &mut Display<'_>) -> Result<(), ClockError> {
+    let current = CURRENT.load(Ordering::Relaxed);
+    let target = TARGET.load(Ordering::Relaxed).min(LIMIT.load(Ordering::Relaxed));
+    if current == target {
+        return Ok(());
+    }
+
+    let now = now_ms();
+    if now.wrapping_sub(LAST_STEP.load(Ordering::Relaxed)) < FADE_STEP_MS {
+        return Ok(());
+    }
+    LAST_STEP.store(now, Ordering::Relaxed);
+
+    let level = if current < target { current + 1 } else { current - 1 };
+    set_display_intensity(display, level).await?;
+    CURRENT.store(level, Ordering::Relaxed);
+    Ok(())
+}
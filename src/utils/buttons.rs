@@ -1,4 +1,5 @@
 use super::Mode;
+use core::cell::Cell;
 use core::ops::ControlFlow;
 
 use defmt::info;
@@ -13,16 +14,75 @@ use embassy_stm32::peripherals::PA2;
 use embassy_stm32::peripherals::PA1;
 
 use embassy_stm32::gpio::Input;
-use embassy_time::Timer;
+use embassy_time::{Duration, Instant, Timer};
 
-pub const HOLD_TIME: u16 = 2000;
-pub const BUTTON_CLICK_TIME: u16 = 150;
+use super::watchdog::{self, Activity};
+
+pub const HOLD_TIME: Duration = Duration::from_millis(2000);
+/// Presses held longer than this are no longer clicks
+pub const BUTTON_CLICK_TIME: Duration = Duration::from_millis(300);
+
+/// How long main or exit has been held, fed by `Buttons::button_hold`.
+#[derive(Default)]
+pub struct HoldTimer {
+    since: Option<Instant>,
+}
+
+impl HoldTimer {
+    pub fn held_for(&self) -> Duration {
+        self.since.map_or(Duration::from_ticks(0), |since| since.elapsed())
+    }
+
+    /// Still short enough to count as a click, or not pressed at all.
+    pub fn is_click(&self) -> bool {
+        self.held_for() <= BUTTON_CLICK_TIME
+    }
+}
+
+/// How a held up/down button repeats while adjusting a value.
+pub struct RepeatConfig {
+    /// Held this long before the first repeat
+    pub delay: Duration,
+    /// Time between repeats
+    pub interval: Duration,
+    /// Steps per repeat once held for at least the given time, in increasing order
+    pub stages: [(Duration, u32); 2],
+}
+
+impl RepeatConfig {
+    fn steps(&self, held: Duration) -> u32 {
+        self.stages
+            .iter()
+            .filter(|(after, _)| held >= *after)
+            .map(|(_, steps)| *steps)
+            .last()
+            .unwrap_or(1)
+    }
+}
+
+pub const REPEAT: RepeatConfig = RepeatConfig {
+    delay: Duration::from_millis(500),
+    interval: Duration::from_millis(200),
+    stages: [(Duration::from_secs(1), 5), (Duration::from_secs(3), 10)],
+};
+
+// Contacts settle within this time after a press
+const DEBOUNCE: Duration = Duration::from_millis(30);
+
+#[derive(Clone, Copy)]
+struct RepeatState {
+    since: Instant,
+    next_repeat: Instant,
+}
 
 pub struct Buttons<'a> {
     main: Input<'a, PA1>,
     down: Input<'a, PA2>,
     up: Input<'a, PA3>,
     exit: Input<'a, PA4>,
+    last_press: Cell<Instant>,
+    up_hold: Cell<Option<RepeatState>>,
+    down_hold: Cell<Option<RepeatState>>,
 }
 
 impl<'a> Buttons<'a> {
@@ -32,11 +92,21 @@ impl<'a> Buttons<'a> {
             down: Input::new(p2, Pull::Up),
             up: Input::new(p3, Pull::Up),
             exit: Input::new(p4, Pull::Up),
+            last_press: Cell::new(Instant::now()),
+            up_hold: Cell::new(None),
+            down_hold: Cell::new(None),
         }
     }
 
+    /// Time since any button was last seen pressed.
+    pub fn idle_for(&self) -> Duration {
+        Instant::now() - self.last_press.get()
+    }
+
     pub async fn main_is_low(&self) -> bool {
+        watchdog::report(Activity::Input);
         if self.main.is_low() {
+            self.last_press.set(Instant::now());
             Timer::after_millis(150).await;
             return true;
         }
@@ -44,7 +114,9 @@ impl<'a> Buttons<'a> {
     }
 
     pub async fn up_is_low(&self) -> bool {
+        watchdog::report(Activity::Input);
         if self.up.is_low() {
+            self.last_press.set(Instant::now());
             Timer::after_millis(150).await;
             return true;
         }
@@ -52,20 +124,58 @@ impl<'a> Buttons<'a> {
     }
 
     pub async fn down_is_low(&self) -> bool {
+        watchdog::report(Activity::Input);
         if self.down.is_low() {
+            self.last_press.set(Instant::now());
             Timer::after_millis(150).await;
             return true;
         }
         false
     }
     pub async fn exit_is_low(&self) -> bool {
+        watchdog::report(Activity::Input);
         if self.exit.is_low() {
+            self.last_press.set(Instant::now());
             Timer::after_millis(150).await;
             return true;
         }
         false
     }
 
+    /// Steps the up button is worth on this poll: 1 for a new press, then repeating
+    /// and speeding up as set in `REPEAT` while it is held, 0 between repeats.
+    pub async fn up_steps(&self) -> u32 {
+        self.repeat(self.up.is_low(), &self.up_hold).await
+    }
+
+    /// Same as `up_steps` for the down button.
+    pub async fn down_steps(&self) -> u32 {
+        self.repeat(self.down.is_low(), &self.down_hold).await
+    }
+
+    async fn repeat(&self, low: bool, hold: &Cell<Option<RepeatState>>) -> u32 {
+        watchdog::report(Activity::Input);
+        if !low {
+            hold.set(None);
+            return 0;
+        }
+
+        let now = Instant::now();
+        self.last_press.set(now);
+        match hold.get() {
+            None => {
+                hold.set(Some(RepeatState { since: now, next_repeat: now + REPEAT.delay }));
+                Timer::after(DEBOUNCE).await;
+                1
+            }
+            Some(held) if now >= held.next_repeat => {
+                hold.set(Some(RepeatState { next_repeat: now + REPEAT.interval, ..held }));
+                REPEAT.steps(now - held.since)
+            }
+            Some(_) => 0,
+        }
+    }
+
     pub async fn any_pin_is_low(&self) -> bool {
         self.main_is_low().await
             || self.exit_is_low().await
@@ -73,35 +183,33 @@ impl<'a> Buttons<'a> {
             || self.down_is_low().await
     }
 
-    pub async fn button_hold(&self, hold_time: &mut u16, main: bool) -> ControlFlow<()> {
-        if main {
-            if self.main_is_low().await {
-                if let Some(value) = Buttons::hold_handler(hold_time) {
-                    return value;
-                }
-            } else {
-                *hold_time = 0;
-            }
-        } else {
-            if self.exit_is_low().await {
-                if let Some(value) = Buttons::hold_handler(hold_time) {
-                    return value;
-                }
-            } else {
-                *hold_time = 0;
+    /// Waits until every button is up again, so a press is not handled twice.
+    /// Gives up after a second, callers still have their own work to report.
+    pub async fn wait_release(&self) {
+        for _ in 0..50 {
+            if self.main.is_high() && self.down.is_high() && self.up.is_high() && self.exit.is_high() {
+                break;
             }
+            watchdog::report(Activity::Input);
+            Timer::after_millis(20).await;
         }
-
-        ControlFlow::Continue(())
     }
 
-    pub(crate) fn hold_handler(hold_time: &mut u16) -> Option<ControlFlow<()>> {
-        *hold_time += 150;
-        if *hold_time >= HOLD_TIME {
-            info! {"Held for = {}", *hold_time};
-            return Some(ControlFlow::Break(()));
+    /// Breaks once main (or exit) has been held for `HOLD_TIME`.
+    pub async fn button_hold(&self, hold: &mut HoldTimer, main: bool) -> ControlFlow<()> {
+        let pressed = if main { self.main_is_low().await } else { self.exit_is_low().await };
+        if !pressed {
+            hold.since = None;
+            return ControlFlow::Continue(());
+        }
+
+        // Counted from when the press was first seen, not from after its debounce
+        let since = *hold.since.get_or_insert(self.last_press.get());
+        if since.elapsed() >= HOLD_TIME {
+            info! {"Held for = {} ms", since.elapsed().as_millis()};
+            return ControlFlow::Break(());
         }
-        None
+        ControlFlow::Continue(())
     }
 
     pub async fn mode_change<T: Mode>(&self, mode: &mut T, standard: bool) -> bool {
@@ -0,0 +1,262 @@
+use core::fmt::Write;
+use core::mem::MaybeUninit;
+use core::panic::PanicInfo;
+use core::ptr::{addr_of, addr_of_mut, read_volatile};
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use defmt::{error, info, Debug2Format};
+use embassy_stm32::pac;
+
+use super::backup_rtc::from_counter;
+
+static I2C_RECOVERY_ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+static I2C_RECOVERY_SUCCESSES: AtomicU32 = AtomicU32::new(0);
+
+pub fn record_i2c_recovery(recovered: bool) {
+    I2C_RECOVERY_ATTEMPTS.fetch_add(1, Ordering::Relaxed);
+    if recovered {
+        I2C_RECOVERY_SUCCESSES.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Number of I2C bus recovery attempts and how many of them brought the DS1307 back.
+pub fn i2c_recovery_stats() -> (u32, u32) {
+    (
+        I2C_RECOVERY_ATTEMPTS.load(Ordering::Relaxed),
+        I2C_RECOVERY_SUCCESSES.load(Ordering::Relaxed),
+    )
+}
+
+const RESET_LOG_MAGIC: u32 = 0x5245_5345;
+const RESET_LOG_LEN: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub enum ResetCause {
+    PowerOn,
+    Pin,
+    Software,
+    IndependentWatchdog,
+    WindowWatchdog,
+    LowPower,
+    Unknown,
+}
+
+impl ResetCause {
+    /// Two characters for the diagnostics screen.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ResetCause::PowerOn => "PO",
+            ResetCause::Pin => "PI",
+            ResetCause::Software => "SW",
+            ResetCause::IndependentWatchdog => "WD",
+            ResetCause::WindowWatchdog => "WW",
+            ResetCause::LowPower => "LP",
+            ResetCause::Unknown => "--",
+        }
+    }
+
+    fn from_raw(raw: u8) -> Self {
+        match raw {
+            0 => ResetCause::PowerOn,
+            1 => ResetCause::Pin,
+            2 => ResetCause::Software,
+            3 => ResetCause::IndependentWatchdog,
+            4 => ResetCause::WindowWatchdog,
+            5 => ResetCause::LowPower,
+            _ => ResetCause::Unknown,
+        }
+    }
+
+    fn raw(&self) -> u8 {
+        match self {
+            ResetCause::PowerOn => 0,
+            ResetCause::Pin => 1,
+            ResetCause::Software => 2,
+            ResetCause::IndependentWatchdog => 3,
+            ResetCause::WindowWatchdog => 4,
+            ResetCause::LowPower => 5,
+            ResetCause::Unknown => 0xff,
+        }
+    }
+}
+
+// Lives in RAM that is not zeroed at startup, so it survives everything but power loss
+struct ResetLog {
+    magic: u32,
+    watchdog_resets: u32,
+    causes: [u8; RESET_LOG_LEN],
+}
+
+#[link_section = ".uninit.diagnostics"]
+static mut RESET_LOG: MaybeUninit<ResetLog> = MaybeUninit::uninit();
+
+/// Reads the reset flags from RCC, clears them and appends the cause to the reset log.
+/// Has to be called once at boot, before anything else touches RCC CSR.
+pub fn record_reset_cause() {
+    let csr = pac::RCC.csr().read();
+    let cause = if csr.lpwrrstf() {
+        ResetCause::LowPower
+    } else if csr.wwdgrstf() {
+        ResetCause::WindowWatchdog
+    } else if csr.iwdgrstf() {
+        ResetCause::IndependentWatchdog
+    } else if csr.sftrstf() {
+        ResetCause::Software
+    } else if csr.porrstf() {
+        ResetCause::PowerOn
+    } else if csr.pinrstf() {
+        ResetCause::Pin
+    } else {
+        ResetCause::Unknown
+    };
+    pac::RCC.csr().modify(|w| w.set_rmvf(true));
+
+    // SAFETY: only touched from the main task. The log is read only after the magic
+    // shows a previous boot wrote it, otherwise it is written from scratch first.
+    let log = unsafe {
+        let log = &mut *addr_of_mut!(RESET_LOG);
+        if !reset_log_valid() || cause == ResetCause::PowerOn {
+            log.write(ResetLog {
+                magic: RESET_LOG_MAGIC,
+                watchdog_resets: 0,
+                causes: [ResetCause::Unknown.raw(); RESET_LOG_LEN],
+            });
+        }
+        log.assume_init_mut()
+    };
+
+    log.causes.copy_within(0..RESET_LOG_LEN - 1, 1);
+    log.causes[0] = cause.raw();
+    if cause == ResetCause::IndependentWatchdog {
+        log.watchdog_resets = log.watchdog_resets.saturating_add(1);
+    }
+
+    info! {"Reset cause: {}, watchdog resets: {}", cause, log.watchdog_resets};
+}
+
+fn reset_log_valid() -> bool {
+    // SAFETY: reads a plain integer through a raw pointer, no reference to the
+    // possibly uninitialised log is made
+    unsafe { read_volatile(addr_of!((*(*addr_of!(RESET_LOG)).as_ptr()).magic)) == RESET_LOG_MAGIC }
+}
+
+fn reset_log() -> Option<&'static ResetLog> {
+    // SAFETY: a valid magic means `record_reset_cause` initialised the log
+    reset_log_valid().then(|| unsafe { (*addr_of!(RESET_LOG)).assume_init_ref() })
+}
+
+/// Most recent reset causes, newest first.
+pub fn reset_history() -> [ResetCause; RESET_LOG_LEN] {
+    reset_log().map_or([ResetCause::Unknown; RESET_LOG_LEN], |log| log.causes.map(ResetCause::from_raw))
+}
+
+pub fn watchdog_resets() -> u32 {
+    reset_log().map_or(0, |log| log.watchdog_resets)
+}
+
+const CRASH_MAGIC: u32 = 0x4352_4153;
+const CRASH_FILE_LEN: usize = 24;
+const CRASH_MESSAGE_LEN: usize = 48;
+
+/// Last panic, kept in uninitialised RAM across the reboot that follows it.
+#[derive(Clone, Copy)]
+pub struct CrashRecord {
+    magic: u32,
+    pub line: u32,
+    /// Internal RTC counter, seconds since 2000-01-01
+    pub timestamp: u32,
+    file: [u8; CRASH_FILE_LEN],
+    file_len: u8,
+    message: [u8; CRASH_MESSAGE_LEN],
+    message_len: u8,
+}
+
+impl CrashRecord {
+    pub fn file(&self) -> &str {
+        let len = (self.file_len as usize).min(CRASH_FILE_LEN);
+        core::str::from_utf8(&self.file[..len]).unwrap_or("?")
+    }
+
+    pub fn message(&self) -> &str {
+        let len = (self.message_len as usize).min(CRASH_MESSAGE_LEN);
+        core::str::from_utf8(&self.message[..len]).unwrap_or("?")
+    }
+}
+
+#[link_section = ".uninit.crash"]
+static mut CRASH_RECORD: MaybeUninit<CrashRecord> = MaybeUninit::uninit();
+
+// Truncating writer, panic messages longer than the buffer are cut off
+struct FixedWriter<'b> {
+    buffer: &'b mut [u8],
+    len: usize,
+}
+
+impl Write for FixedWriter<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for c in s.chars() {
+            if self.len + c.len_utf8() > self.buffer.len() {
+                break;
+            }
+            c.encode_utf8(&mut self.buffer[self.len..]);
+            self.len += c.len_utf8();
+        }
+        Ok(())
+    }
+}
+
+pub fn record_crash(info: &PanicInfo, timestamp: u32) {
+    // SAFETY: called with interrupts disabled from the panic handler, the record is
+    // written in full before it is referenced
+    let record = unsafe {
+        (*addr_of_mut!(CRASH_RECORD)).write(CrashRecord {
+            magic: 0,
+            line: 0,
+            timestamp: 0,
+            file: [0; CRASH_FILE_LEN],
+            file_len: 0,
+            message: [0; CRASH_MESSAGE_LEN],
+            message_len: 0,
+        })
+    };
+
+    record.timestamp = timestamp;
+    record.line = info.location().map_or(0, |location| location.line());
+
+    // Keep the end of the path, it holds the file name
+    let file = info.location().map_or("?", |location| location.file());
+    let mut start = file.len().saturating_sub(CRASH_FILE_LEN);
+    while !file.is_char_boundary(start) {
+        start += 1;
+    }
+    record.file[..file.len() - start].copy_from_slice(&file.as_bytes()[start..]);
+    record.file_len = (file.len() - start) as u8;
+
+    let mut writer = FixedWriter { buffer: &mut record.message, len: 0 };
+    let _ = write!(writer, "{}", info.message());
+    record.message_len = writer.len as u8;
+
+    record.magic = CRASH_MAGIC;
+}
+
+pub fn crash_record() -> Option<CrashRecord> {
+    // SAFETY: the magic is read through a raw pointer, the record is referenced only
+    // once it shows `record_crash` wrote it
+    unsafe {
+        let record = addr_of!(CRASH_RECORD);
+        if read_volatile(addr_of!((*(*record).as_ptr()).magic)) == CRASH_MAGIC {
+            Some(*(*record).assume_init_ref())
+        } else {
+            None
+        }
+    }
+}
+
+/// Prints the saved crash over defmt, the serial console of this board.
+pub fn log_crash_record() {
+    if let Some(record) = crash_record() {
+        let datetime = from_counter(record.timestamp);
+        error! {"Last crash at {}: {}:{} {}",
+            Debug2Format(&datetime), record.file(), record.line, record.message()};
+    }
+}
@@ -0,0 +1,45 @@
+use crate::error::ClockError;
+use super::matrix_display::MatrixDisplay;
+
+/// Front panel the clock faces render through.
+///
+/// Faces always draw four 8x8 matrices into a `MatrixDisplay`, each backend turns
+/// that frame into whatever its hardware shows. Exactly one backend is compiled in,
+/// selected with a `display-*` cargo feature.
+#[allow(async_fn_in_trait)]
+pub trait DisplayBackend {
+    async fn write_frame(&mut self, matrices: &MatrixDisplay) -> Result<(), ClockError>;
+    /// Brightness in MAX7219 steps, 0 to 15.
+    async fn set_intensity(&mut self, intensity: u8) -> Result<(), ClockError>;
+    async fn power_on(&mut self) -> Result<(), ClockError>;
+    async fn power_off(&mut self) -> Result<(), ClockError>;
+}
+
+#[cfg(feature = "display-max7219")]
+pub type Display<'a> = super::max7219_spi::Max7219Spi<'a>;
+#[cfg(feature = "display-max7219-7seg")]
+pub type Display<'a> = super::max7219_segment::Max7219Segment<'a>;
+#[cfg(feature = "display-ht16k33")]
+pub type Display<'a> = super::ht16k33::Ht16k33<'a>;
+#[cfg(feature = "display-ssd1306")]
+pub type Display<'a> = super::ssd1306::Ssd1306<'a>;
+
+#[cfg(not(any(
+    feature = "display-max7219",
+    feature = "display-max7219-7seg",
+    feature = "display-ht16k33",
+    feature = "display-ssd1306",
+)))]
+compile_error!("Select a display backend with one of the display-* features");
+
+#[cfg(any(
+    all(feature = "display-max7219", feature = "display-max7219-7seg"),
+    all(feature = "display-max7219", feature = "display-ht16k33"),
+    all(feature = "display-max7219", feature = "display-ssd1306"),
+    all(feature = "display-max7219-7seg", feature = "display-ht16k33"),
+    all(feature = "display-max7219-7seg", feature = "display-ssd1306"),
+    all(feature = "display-ht16k33", feature = "display-ssd1306"),
+))]
+compile_error!(
+    "Select only one display backend, build with --no-default-features when choosing one other than display-max7219"
+);
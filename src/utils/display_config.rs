@@ -0,0 +1,132 @@
+pub const MAX_MODULES: usize = 8;
+// Faces always render four 8x8 matrices, first to fourth
+pub const FRAME_MATRICES: usize = 4;
+
+/// Clockwise rotation of a module relative to the generic MAX7219 boards.
+#[derive(Clone, Copy)]
+pub enum Rotation {
+    None,
+    Cw90,
+    Half,
+    Ccw90,
+}
+
+#[derive(Clone, Copy)]
+pub struct ModuleConfig {
+    pub rotation: Rotation,
+    pub flip_columns: bool,
+    pub flip_rows: bool,
+}
+
+impl ModuleConfig {
+    pub const fn new(rotation: Rotation) -> Self {
+        ModuleConfig {
+            rotation,
+            flip_columns: false,
+            flip_rows: false,
+        }
+    }
+}
+
+/// Physical layout of the MAX7219 chain.
+///
+/// `chain_order[position]` is the frame matrix shown by the module at that position
+/// of the chain buffer, `None` keeps the module blank.
+#[derive(Clone, Copy)]
+pub struct DisplayConfig {
+    pub modules: usize,
+    pub chain_order: [Option<usize>; MAX_MODULES],
+    pub module: [ModuleConfig; MAX_MODULES],
+}
+
+const UPRIGHT: [ModuleConfig; MAX_MODULES] = [ModuleConfig::new(Rotation::None); MAX_MODULES];
+
+/// Four generic modules, the original front panel.
+pub const GENERIC_4: DisplayConfig = DisplayConfig {
+    modules: 4,
+    chain_order: [Some(0), Some(1), Some(2), Some(3), None, None, None, None],
+    module: UPRIGHT,
+};
+
+/// Four generic modules, panel mounted upside down.
+pub const GENERIC_4_UPSIDE_DOWN: DisplayConfig = DisplayConfig {
+    modules: 4,
+    chain_order: [Some(3), Some(2), Some(1), Some(0), None, None, None, None],
+    module: [ModuleConfig::new(Rotation::Half); MAX_MODULES],
+};
+
+/// FC-16 board, modules are mounted rotated by 90 degrees.
+pub const FC16_4: DisplayConfig = DisplayConfig {
+    modules: 4,
+    chain_order: [Some(0), Some(1), Some(2), Some(3), None, None, None, None],
+    module: [ModuleConfig::new(Rotation::Ccw90); MAX_MODULES],
+};
+
+/// FC-16 board mounted upside down.
+pub const FC16_4_UPSIDE_DOWN: DisplayConfig = DisplayConfig {
+    modules: 4,
+    chain_order: [Some(3), Some(2), Some(1), Some(0), None, None, None, None],
+    module: [ModuleConfig::new(Rotation::Cw90); MAX_MODULES],
+};
+
+/// Eight generic modules, the face is centered and the outer modules stay dark.
+pub const GENERIC_8: DisplayConfig = DisplayConfig {
+    modules: 8,
+    chain_order: [None, None, Some(0), Some(1), Some(2), Some(3), None, None],
+    module: UPRIGHT,
+};
+
+/// Layout of the chain, selected with a `layout-*` cargo feature, `GENERIC_4` without one.
+pub const DISPLAY_CONFIG: DisplayConfig = if cfg!(feature = "layout-fc16") {
+    FC16_4
+} else if cfg!(feature = "layout-fc16-upside-down") {
+    FC16_4_UPSIDE_DOWN
+} else if cfg!(feature = "layout-upside-down") {
+    GENERIC_4_UPSIDE_DOWN
+} else if cfg!(feature = "layout-generic-8") {
+    GENERIC_8
+} else {
+    GENERIC_4
+};
+
+impl DisplayConfig {
+    /// Maps the four frame matrices onto the chain, applying each module's orientation.
+    pub fn map_frame(&self, frame: &[[u8; 8]; FRAME_MATRICES]) -> [[u8; 8]; MAX_MODULES] {
+        let mut chain = [[0; 8]; MAX_MODULES];
+
+        for position in 0..self.modules.min(MAX_MODULES) {
+            if let Some(matrix) = self.chain_order[position].filter(|&matrix| matrix < FRAME_MATRICES) {
+                chain[position] = orient(&frame[matrix], &self.module[position]);
+            }
+        }
+        chain
+    }
+}
+
+fn orient(bitmap: &[u8; 8], config: &ModuleConfig) -> [u8; 8] {
+    let mut oriented = [0; 8];
+
+    for (row, bits) in bitmap.iter().enumerate() {
+        for column in 0..8 {
+            if bits & (0x80 >> column) == 0 {
+                continue;
+            }
+
+            let (mut r, mut c) = match config.rotation {
+                Rotation::None => (row, column),
+                Rotation::Cw90 => (column, 7 - row),
+                Rotation::Half => (7 - row, 7 - column),
+                Rotation::Ccw90 => (7 - column, row),
+            };
+            if config.flip_rows {
+                r = 7 - r;
+            }
+            if config.flip_columns {
+                c = 7 - c;
+            }
+
+            oriented[r] |= 0x80 >> c;
+        }
+    }
+    oriented
+}
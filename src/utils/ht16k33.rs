@@ -0,0 +1,76 @@
+use embassy_stm32::dma::NoDma;
+use embassy_stm32::i2c::{Error, I2c};
+use embassy_stm32::peripherals::I2C2;
+
+use crate::error::ClockError;
+use super::display_backend::DisplayBackend;
+use super::matrix_display::MatrixDisplay;
+
+/// One 8x8 backpack per frame matrix, first to fourth.
+pub const HT16K33_ADDRESSES: [u8; 4] = [0x70, 0x71, 0x72, 0x73];
+
+const OSCILLATOR_ON: u8 = 0x21;
+const DISPLAY_SETUP: u8 = 0x80;
+const DISPLAY_ON: u8 = 0x01;
+const DIMMING: u8 = 0xE0;
+
+/// Four HT16K33 8x8 LED backpacks on I2C2 (PB10 SCL, PB11 SDA).
+///
+/// A backpack is only rewritten when its matrix changed since the last frame.
+pub struct Ht16k33<'a> {
+    i2c: I2c<'a, I2C2, NoDma, NoDma>,
+    shadow: [Option<[u8; 8]>; 4],
+}
+
+impl<'a> Ht16k33<'a> {
+    pub fn new(i2c: I2c<'a, I2C2, NoDma, NoDma>) -> Result<Self, Error> {
+        let mut display = Ht16k33 {
+            i2c,
+            shadow: [None; 4],
+        };
+
+        display.command_all(OSCILLATOR_ON)?;
+        display.command_all(DISPLAY_SETUP)?;
+
+        Ok(display)
+    }
+
+    fn command_all(&mut self, command: u8) -> Result<(), Error> {
+        for address in HT16K33_ADDRESSES {
+            self.i2c.blocking_write(address, &[command])?;
+        }
+        Ok(())
+    }
+}
+
+impl DisplayBackend for Ht16k33<'_> {
+    async fn write_frame(&mut self, matrices: &MatrixDisplay) -> Result<(), ClockError> {
+        for (index, matrix) in matrices.frame().iter().enumerate() {
+            if self.shadow[index] == Some(*matrix) {
+                continue;
+            }
+
+            // Display RAM starts at 0x00, two bytes per row and column 0 in the LSB
+            let mut buffer = [0; 17];
+            for (row, bits) in matrix.iter().enumerate() {
+                buffer[1 + row * 2] = bits.reverse_bits();
+            }
+
+            self.i2c.blocking_write(HT16K33_ADDRESSES[index], &buffer)?;
+            self.shadow[index] = Some(*matrix);
+        }
+        Ok(())
+    }
+
+    async fn set_intensity(&mut self, intensity: u8) -> Result<(), ClockError> {
+        Ok(self.command_all(DIMMING | (intensity & 0x0F))?)
+    }
+
+    async fn power_on(&mut self) -> Result<(), ClockError> {
+        Ok(self.command_all(DISPLAY_SETUP | DISPLAY_ON)?)
+    }
+
+    async fn power_off(&mut self) -> Result<(), ClockError> {
+        Ok(self.command_all(DISPLAY_SETUP)?)
+    }
+}
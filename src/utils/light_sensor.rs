@@ -0,0 +1,72 @@
+use core::sync::atomic::{AtomicU16, AtomicU8, Ordering};
+
+use embassy_stm32::adc::{Adc, SampleTime};
+use embassy_stm32::peripherals::{ADC1, PA0};
+use embassy_time::Timer;
+
+use crate::settings::{self, BrightnessMode, LightCurve};
+use super::brightness;
+
+const SAMPLE_PERIOD_MS: u64 = 250;
+// Exponential average over roughly the last two seconds of samples
+const FILTER_SHIFT: u32 = 3;
+// Reading has to move this far past the point where the level changes
+const HYSTERESIS: i32 = 60;
+
+static READING: AtomicU16 = AtomicU16::new(0);
+static LEVEL: AtomicU8 = AtomicU8::new(0);
+
+/// Filtered light reading, 0 to 4095.
+pub fn reading() -> u16 {
+    READING.load(Ordering::Relaxed)
+}
+
+/// Display level the light sensor asks for.
+pub fn level() -> u8 {
+    LEVEL.load(Ordering::Relaxed)
+}
+
+/// Samples the photoresistor divider on PA0 (ADC1_IN0) and, while the sensor picks the
+/// brightness, hands its level to the display.
+#[embassy_executor::task]
+pub async fn sample(mut adc: Adc<'static, ADC1>, mut pin: PA0) {
+    adc.set_sample_time(SampleTime::Cycles239_5);
+
+    // Filter keeps extra fraction bits
+    let mut filtered = (adc.read(&mut pin) as u32) << FILTER_SHIFT;
+    loop {
+        let sample = adc.read(&mut pin) as u32;
+        filtered = filtered - (filtered >> FILTER_SHIFT) + sample;
+        let reading = (filtered >> FILTER_SHIFT) as u16;
+        READING.store(reading, Ordering::Relaxed);
+
+        let settings = settings::get();
+        let level = next_level(&settings.light, reading, level());
+        LEVEL.store(level, Ordering::Relaxed);
+        if settings.brightness_mode == BrightnessMode::Sensor {
+            brightness::set_target(level);
+        }
+
+        Timer::after_millis(SAMPLE_PERIOD_MS).await;
+    }
+}
+
+// A new level is only taken when the reading still maps past the current level after
+// being pulled back by the hysteresis, so a reading on a boundary does not flicker
+fn next_level(curve: &LightCurve, reading: u16, current: u8) -> u8 {
+    let level = curve.level(reading);
+    if level == current {
+        return current;
+    }
+
+    // Pull back towards the current level, whichever way the sensor is wired
+    let rises_with_reading = (curve.bright_level > curve.dark_level) == (curve.bright_reading > curve.dark_reading);
+    let reading_went_up = (level > current) == rises_with_reading;
+    let pull = if reading_went_up { -HYSTERESIS } else { HYSTERESIS };
+    let pulled = (reading as i32 + pull).clamp(0, settings::ADC_MAX as i32) as u16;
+
+    match curve.level(pulled) {
+        pulled_level if pulled_level == current => current,
+        _ => level,
+    }
+}
@@ -1,13 +1,11 @@
 use super::shift_bits;
-use defmt::info;
-use embassy_stm32::peripherals::PA5;
-use embassy_stm32::peripherals::PB0;
-use embassy_stm32::peripherals::PA7;
-use embassy_stm32::gpio::Output;
-use max7219::connectors::PinConnector;
-use max7219::DataError;
-use max7219::MAX7219;
+use defmt::error;
 use super::symbols;
+use super::{Display, DisplayBackend};
+use crate::error::ClockError;
+use crate::locale::{self, Text};
+use super::brightness;
+use super::watchdog::{self, Activity};
 
 
 pub struct MatrixDisplay {
@@ -27,32 +25,121 @@ impl MatrixDisplay {
         }
     }
 
-    pub fn display_update(
+    pub async fn display_update(
         &mut self,
-        display: &mut MAX7219<PinConnector<Output<'_, PA7>, Output<'_, PB0>, Output<'_, PA5>>>,
+        display: &mut Display<'_>,
     ) {
-        if let Err(_) = self.write_to_display(display) {
-            self.set_error();
+        if let Err(err) = self.write_to_display(display).await {
+            self.set_error(err);
         }
+        watchdog::report(Activity::Display);
     }
 
-    pub(crate) fn write_to_display(
+    pub(crate) async fn write_to_display(
         &self,
-        display: &mut MAX7219<PinConnector<Output<'_, PA7>, Output<'_, PB0>, Output<'_, PA5>>>,
-    ) -> Result<(), DataError> {
-        display.write_raw(0, &self.first_matrix)?;
-        display.write_raw(1, &self.second_matrix)?;
-        display.write_raw(2, &self.third_matrix)?;
-        display.write_raw(3, &self.fourth_matrix)?;
-        Ok(())
+        display: &mut Display<'_>,
+    ) -> Result<(), ClockError> {
+        display.write_frame(self).await?;
+        brightness::fade(display).await
     }
 
-    pub fn set_error(&mut self) {
-        self.first_matrix = symbols::Letters::E.bytes();
-        self.second_matrix = symbols::Letters::R.bytes();
-        self.third_matrix = symbols::Letters::R.bytes();
-        self.fourth_matrix = symbols::EXCLAMETION_MARK;
-        info! {"Error"};
+    pub fn frame(&self) -> [[u8; 8]; 4] {
+        [self.first_matrix, self.second_matrix, self.third_matrix, self.fourth_matrix]
+    }
+
+    pub fn set_frame(&mut self, frame: [[u8; 8]; 4]) {
+        [self.first_matrix, self.second_matrix, self.third_matrix, self.fourth_matrix] = frame;
+    }
+
+    /// Lights one pixel, columns count from the left edge of the first matrix.
+    pub fn set_pixel(&mut self, column: usize, row: usize) {
+        let matrix = match column / 8 {
+            0 => &mut self.first_matrix,
+            1 => &mut self.second_matrix,
+            2 => &mut self.third_matrix,
+            3 => &mut self.fourth_matrix,
+            _ => return,
+        };
+        if let Some(bits) = matrix.get_mut(row) {
+            *bits |= 0x80 >> (column % 8);
+        }
+    }
+
+    pub fn set_error(&mut self, err: ClockError) {
+        self.set_text(err.code());
+        error! {"{}: {}", err.code(), err};
+    }
+
+    /// First four characters of `text`, one per matrix.
+    pub fn set_text(&mut self, text: &str) {
+        let mut chars = text.chars();
+        let mut next = || chars.next().map_or(symbols::BLANK, symbols::glyph);
+
+        self.first_matrix = next();
+        self.second_matrix = next();
+        self.third_matrix = next();
+        self.fourth_matrix = next();
+    }
+
+    /// Window of `text` starting `offset` pixels from its left edge, for scrolling.
+    /// A negative offset leaves that many blank columns before the text.
+    pub fn set_scrolled_text(&mut self, text: &str, offset: isize) {
+        let mut frame = [symbols::BLANK; 4];
+
+        for (index, c) in text.chars().enumerate() {
+            let glyph = symbols::glyph(c);
+
+            for column in 0..8 {
+                let target = (index * 8 + column) as isize - offset;
+                if !(0..32).contains(&target) {
+                    continue;
+                }
+
+                let target = target as usize;
+                for (row, bits) in glyph.iter().enumerate() {
+                    if bits & (0x80 >> column) != 0 {
+                        frame[target / 8][row] |= 0x80 >> (target % 8);
+                    }
+                }
+            }
+        }
+
+        [self.first_matrix, self.second_matrix, self.third_matrix, self.fourth_matrix] = frame;
+    }
+
+    /// Window of `text` repeated end to end, `offset` pixels in. Scrolls text of any
+    /// length round and round without a second copy of it.
+    pub fn set_looped_text(&mut self, text: &str, offset: usize) {
+        self.set_frame([symbols::BLANK; 4]);
+        let columns = text.chars().count() * 8;
+        if columns == 0 {
+            return;
+        }
+
+        for (index, c) in text.chars().enumerate() {
+            let glyph = symbols::glyph(c);
+
+            for column in 0..8 {
+                let mut target = (index * 8 + column + columns - offset % columns) % columns;
+                while target < 32 {
+                    for (row, bits) in glyph.iter().enumerate() {
+                        if bits & (0x80 >> column) != 0 {
+                            self.set_pixel(target, row);
+                        }
+                    }
+                    target += columns;
+                }
+            }
+        }
+    }
+
+    pub fn set_time_warning(&mut self) {
+        self.set_text(locale::text(Text::SetTime));
+    }
+
+    // Single pixel in the bottom right corner, time comes from the internal RTC
+    pub fn set_degraded(&mut self) {
+        self.fourth_matrix[7] |= 0x01;
     }
 
     pub fn matrix_shift(&mut self, shift: u8) {
@@ -0,0 +1,126 @@
+use embassy_stm32::dma::NoDma;
+use embassy_stm32::gpio::Output;
+use embassy_stm32::peripherals::{DMA1_CH3, PB0, SPI1};
+use embassy_stm32::spi::{Error, Spi};
+
+use crate::error::ClockError;
+use super::display_backend::DisplayBackend;
+use super::matrix_display::MatrixDisplay;
+use super::symbols::{self, Letters, DIGITS};
+
+const DIGIT_0: u8 = 0x01;
+const DECODE_MODE: u8 = 0x09;
+const INTENSITY: u8 = 0x0A;
+const SCAN_LIMIT: u8 = 0x0B;
+const SHUTDOWN: u8 = 0x0C;
+const DISPLAY_TEST: u8 = 0x0F;
+
+// Code B font of the MAX7219 decoder
+const CODE_MINUS: u8 = 0x0A;
+const CODE_E: u8 = 0x0B;
+const CODE_H: u8 = 0x0C;
+const CODE_L: u8 = 0x0D;
+const CODE_P: u8 = 0x0E;
+const CODE_BLANK: u8 = 0x0F;
+const DECIMAL_POINT: u8 = 0x80;
+
+// Outer columns carry the blinking dots, they are ignored when matching glyphs
+const GLYPH_MASK: u8 = 0x7E;
+
+/// Single MAX7219 in BCD decode mode driving four 7-segment digits, on the same
+/// SPI1 pins as the matrix chain.
+///
+/// Faces still render matrices, every matrix is matched back to the digit or
+/// letter it shows. Anything the Code B font cannot show is left blank.
+pub struct Max7219Segment<'a> {
+    spi: Spi<'a, SPI1, DMA1_CH3, NoDma>,
+    cs: Output<'a, PB0>,
+    digits: [u8; 4],
+}
+
+impl<'a> Max7219Segment<'a> {
+    pub async fn new(spi: Spi<'a, SPI1, DMA1_CH3, NoDma>, cs: Output<'a, PB0>) -> Result<Self, Error> {
+        let mut display = Max7219Segment {
+            spi,
+            cs,
+            digits: [CODE_BLANK; 4],
+        };
+
+        display.write(DISPLAY_TEST, 0x00).await?;
+        display.write(SCAN_LIMIT, 0x03).await?;
+        display.write(DECODE_MODE, 0x0F).await?;
+        for digit in 0..4 {
+            display.write(DIGIT_0 + digit, CODE_BLANK).await?;
+        }
+        display.write(SHUTDOWN, 0x00).await?;
+
+        Ok(display)
+    }
+
+    async fn write(&mut self, register: u8, data: u8) -> Result<(), Error> {
+        self.cs.set_low();
+        let result = self.spi.write(&[register, data]).await;
+        self.cs.set_high();
+        result
+    }
+}
+
+impl DisplayBackend for Max7219Segment<'_> {
+    async fn write_frame(&mut self, matrices: &MatrixDisplay) -> Result<(), ClockError> {
+        let frame = matrices.frame();
+
+        for (digit, matrix) in frame.iter().enumerate() {
+            let mut code = code_b(matrix);
+            // Colon between hours and minutes lights the point of the second digit
+            if digit == 1 && frame[1][1] & 0x01 != 0 {
+                code |= DECIMAL_POINT;
+            }
+
+            if self.digits[digit] != code {
+                self.write(DIGIT_0 + digit as u8, code).await?;
+                self.digits[digit] = code;
+            }
+        }
+        Ok(())
+    }
+
+    async fn set_intensity(&mut self, intensity: u8) -> Result<(), ClockError> {
+        Ok(self.write(INTENSITY, intensity & 0x0F).await?)
+    }
+
+    async fn power_on(&mut self) -> Result<(), ClockError> {
+        Ok(self.write(SHUTDOWN, 0x01).await?)
+    }
+
+    async fn power_off(&mut self) -> Result<(), ClockError> {
+        Ok(self.write(SHUTDOWN, 0x00).await?)
+    }
+}
+
+fn code_b(matrix: &[u8; 8]) -> u8 {
+    let letters = [
+        (Letters::E.bytes(), CODE_E),
+        (Letters::H.bytes(), CODE_H),
+        (Letters::L.bytes(), CODE_L),
+        (Letters::P.bytes(), CODE_P),
+        (symbols::MINUS, CODE_MINUS),
+    ];
+
+    let digits = DIGITS.iter().zip(0u8..);
+    let letters = letters.iter().map(|(glyph, code)| (glyph, *code));
+
+    digits
+        .chain(letters)
+        .find(|(glyph, _)| matches_glyph(matrix, glyph))
+        .map_or(CODE_BLANK, |(_, code)| code)
+}
+
+// Faces shift glyphs right by up to two columns to make room for the dots
+fn matches_glyph(matrix: &[u8; 8], glyph: &[u8; 8]) -> bool {
+    (0..=2).any(|shift| {
+        matrix
+            .iter()
+            .zip(glyph.iter())
+            .all(|(shown, bits)| shown & GLYPH_MASK == (bits >> shift) & GLYPH_MASK)
+    })
+}
@@ -0,0 +1,139 @@
+use embassy_stm32::dma::NoDma;
+use embassy_stm32::gpio::Output;
+use embassy_stm32::peripherals::{DMA1_CH3, PB0, SPI1};
+use embassy_stm32::spi::{Error, Spi};
+use embassy_time::{Duration, Instant};
+
+use crate::error::ClockError;
+use super::display_backend::DisplayBackend;
+use super::display_config::{DisplayConfig, FRAME_MATRICES, MAX_MODULES};
+use super::matrix_display::MatrixDisplay;
+
+const NOOP: u8 = 0x00;
+const DIGIT_0: u8 = 0x01;
+const DECODE_MODE: u8 = 0x09;
+const INTENSITY: u8 = 0x0A;
+const SCAN_LIMIT: u8 = 0x0B;
+const SHUTDOWN: u8 = 0x0C;
+const DISPLAY_TEST: u8 = 0x0F;
+
+// Noise on the long chain can flip bits in the MAX7219 registers, resend everything now and then
+const FULL_REFRESH_PERIOD: Duration = Duration::from_secs(10);
+
+/// MAX7219 chain on SPI1 (PA5 SCK, PA7 MOSI) with PB0 as chip select.
+///
+/// Every transfer goes out through DMA, so the executor keeps running while a
+/// frame is being shifted into the chain. The last frame sent is kept and only
+/// rows that changed since then are written.
+pub struct Max7219Spi<'a> {
+    spi: Spi<'a, SPI1, DMA1_CH3, NoDma>,
+    cs: Output<'a, PB0>,
+    config: DisplayConfig,
+    shadow: [[u8; 8]; MAX_MODULES],
+    intensity: u8,
+    powered: bool,
+    next_full_refresh: Instant,
+}
+
+impl<'a> Max7219Spi<'a> {
+    pub async fn new(spi: Spi<'a, SPI1, DMA1_CH3, NoDma>, cs: Output<'a, PB0>, config: DisplayConfig) -> Result<Self, Error> {
+        let mut display = Max7219Spi {
+            spi,
+            cs,
+            config,
+            shadow: [[0; 8]; MAX_MODULES],
+            intensity: 0,
+            powered: false,
+            next_full_refresh: Instant::MIN,
+        };
+
+        display.write_matrices(&[[0; 8]; FRAME_MATRICES]).await?;
+
+        Ok(display)
+    }
+
+    /// Writes the four frame matrices, laid out on the chain by the display config.
+    /// Modules whose row did not change get a no-op, rows with no changes are skipped.
+    async fn write_matrices(&mut self, frame: &[[u8; 8]; FRAME_MATRICES]) -> Result<(), Error> {
+        let chain = self.config.map_frame(frame);
+        let modules = self.modules();
+
+        let full = Instant::now() >= self.next_full_refresh;
+        if full {
+            self.write_config().await?;
+            self.next_full_refresh = Instant::now() + FULL_REFRESH_PERIOD;
+        }
+
+        for row in 0..8 {
+            let mut buffer = [NOOP; MAX_MODULES * 2];
+            let mut dirty = false;
+
+            for (module, bitmap) in chain.iter().enumerate().take(modules) {
+                if full || self.shadow[module][row] != bitmap[row] {
+                    buffer[module * 2] = DIGIT_0 + row as u8;
+                    buffer[module * 2 + 1] = bitmap[row];
+                    dirty = true;
+                }
+            }
+
+            if dirty {
+                self.transfer(&buffer).await?;
+                for (module, bitmap) in chain.iter().enumerate().take(modules) {
+                    self.shadow[module][row] = bitmap[row];
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Control registers are only written on change, restore them during a full refresh
+    async fn write_config(&mut self) -> Result<(), Error> {
+        self.write_all(DISPLAY_TEST, 0x00).await?;
+        self.write_all(SCAN_LIMIT, 0x07).await?;
+        self.write_all(DECODE_MODE, 0x00).await?;
+        self.write_all(INTENSITY, self.intensity).await?;
+        self.write_all(SHUTDOWN, self.powered as u8).await
+    }
+
+    async fn write_all(&mut self, register: u8, data: u8) -> Result<(), Error> {
+        let mut buffer = [NOOP; MAX_MODULES * 2];
+        for module in 0..self.modules() {
+            buffer[module * 2] = register;
+            buffer[module * 2 + 1] = data;
+        }
+        self.transfer(&buffer).await
+    }
+
+    fn modules(&self) -> usize {
+        self.config.modules.min(MAX_MODULES)
+    }
+
+    // Chain latches the shifted words on the rising edge of CS
+    async fn transfer(&mut self, buffer: &[u8]) -> Result<(), Error> {
+        self.cs.set_low();
+        let result = self.spi.write(&buffer[..self.modules() * 2]).await;
+        self.cs.set_high();
+        result
+    }
+}
+
+impl DisplayBackend for Max7219Spi<'_> {
+    async fn write_frame(&mut self, matrices: &MatrixDisplay) -> Result<(), ClockError> {
+        Ok(self.write_matrices(&matrices.frame()).await?)
+    }
+
+    async fn set_intensity(&mut self, intensity: u8) -> Result<(), ClockError> {
+        self.intensity = intensity & 0x0F;
+        Ok(self.write_all(INTENSITY, self.intensity).await?)
+    }
+
+    async fn power_on(&mut self) -> Result<(), ClockError> {
+        self.powered = true;
+        Ok(self.write_all(SHUTDOWN, 0x01).await?)
+    }
+
+    async fn power_off(&mut self) -> Result<(), ClockError> {
+        self.powered = false;
+        Ok(self.write_all(SHUTDOWN, 0x00).await?)
+    }
+}
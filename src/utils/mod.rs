@@ -1,8 +1,4 @@
-use embassy_stm32::gpio::Output;
-use embassy_stm32::peripherals::{PA5, PA7, PB0};
-
-use max7219::connectors::PinConnector;
-use max7219::{DataError, MAX7219};
+use crate::error::ClockError;
 pub mod symbols;
 
 pub trait Mode {
@@ -13,6 +9,27 @@ pub trait Mode {
 pub mod buttons;
 pub mod matrix_display;
 pub mod alarm;
+pub mod animation;
+pub mod brightness;
+pub mod display_backend;
+pub mod light_sensor;
+pub mod night;
+pub mod rotation;
+pub mod thermometer;
+#[cfg(feature = "display-max7219")]
+pub mod display_config;
+#[cfg(feature = "display-max7219")]
+pub mod max7219_spi;
+#[cfg(feature = "display-max7219-7seg")]
+pub mod max7219_segment;
+#[cfg(feature = "display-ht16k33")]
+pub mod ht16k33;
+#[cfg(feature = "display-ssd1306")]
+pub mod ssd1306;
+pub mod backup_rtc;
+pub mod diagnostics;
+pub mod rtc;
+pub mod watchdog;
 
 pub fn shift_bits(data: &mut [u8], shift: u8) {
     for i in 0..8 {
@@ -20,14 +37,11 @@ pub fn shift_bits(data: &mut [u8], shift: u8) {
     }
 }
 
-pub fn set_display_intensity(
-    display: &mut MAX7219<PinConnector<Output<'_, PA7>, Output<'_, PB0>, Output<'_, PA5>>>,
-    intensity: u8,
-) -> Result<(), DataError> {
-    display.set_intensity(0, intensity)?;
-    display.set_intensity(1, intensity)?;
-    display.set_intensity(2, intensity)?;
-    display.set_intensity(3, intensity)?;
+pub use display_backend::{Display, DisplayBackend};
 
-    Ok(())
+pub async fn set_display_intensity(
+    display: &mut Display<'_>,
+    intensity: u8,
+) -> Result<(), ClockError> {
+    display.set_intensity(intensity).await
 }
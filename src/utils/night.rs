@@ -0,0 +1,86 @@
+use defmt::info;
+use ds1307::NaiveDateTime;
+use embassy_time::{Duration, Instant};
+
+use crate::error::ClockError;
+use crate::settings::{self, NightIdle, MAX_LEVEL};
+use super::brightness;
+use super::matrix_display::MatrixDisplay;
+use super::{Display, DisplayBackend};
+
+/// Night mode state of the clock face.
+///
+/// During the night window the face is replaced by the idle look until something
+/// wakes the display: a button press, or an alarm going off.
+pub struct Night {
+    dark: bool,
+    powered_off: bool,
+    awake_until: Option<Instant>,
+}
+
+impl Night {
+    pub fn new() -> Self {
+        Night {
+            dark: false,
+            powered_off: false,
+            awake_until: None,
+        }
+    }
+
+    /// Display shows the idle look instead of the face, button presses only wake it.
+    pub fn is_dark(&self) -> bool {
+        self.dark
+    }
+
+    pub fn update(&mut self, datetime: &NaiveDateTime) {
+        if self.awake_until.is_some_and(|until| Instant::now() >= until) {
+            self.awake_until = None;
+        }
+
+        let dark = settings::get().night.contains(datetime) && self.awake_until.is_none();
+        if dark != self.dark {
+            info! {"Night display {}", if dark { "dark" } else { "awake" }};
+        }
+        self.dark = dark;
+    }
+
+    /// Shows the face for `duration`, a zero duration leaves the display dark.
+    pub fn wake(&mut self, duration: Duration) {
+        if duration > Duration::from_ticks(0) {
+            self.awake_until = Some(Instant::now() + duration);
+            self.dark = false;
+        }
+    }
+
+    /// Swaps the face for the idle look while dark and brings the display back afterwards.
+    pub async fn apply(&mut self, matrices: &mut MatrixDisplay, display: &mut Display<'_>) -> Result<(), ClockError> {
+        if !self.dark {
+            return self.leave(display).await;
+        }
+
+        *matrices = MatrixDisplay::new();
+        match settings::get().night.idle {
+            NightIdle::Off => {
+                if !self.powered_off {
+                    display.power_off().await?;
+                    self.powered_off = true;
+                }
+            }
+            NightIdle::Dot => {
+                matrices.fourth_matrix[7] = 0x01;
+                brightness::set_limit(0);
+            }
+        }
+        Ok(())
+    }
+
+    /// Turns the display back on at its normal brightness.
+    pub async fn leave(&mut self, display: &mut Display<'_>) -> Result<(), ClockError> {
+        brightness::set_limit(MAX_LEVEL);
+        if self.powered_off {
+            display.power_on().await?;
+            self.powered_off = false;
+        }
+        Ok(())
+    }
+}
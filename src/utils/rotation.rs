@@ -0,0 +1,76 @@
+use defmt::info;
+use embassy_time::{Duration, Instant};
+
+use crate::settings::{self, Face, ROTATION_SLOTS};
+
+// After a manual face switch the rotation waits this long before taking over again
+const MANUAL_PAUSE: Duration = Duration::from_secs(60);
+
+/// Automatic cycling through the faces set in the rotation settings.
+pub struct Rotation {
+    slot: usize,
+    since: Instant,
+    paused_until: Option<Instant>,
+}
+
+impl Rotation {
+    /// Starts on the first enabled slot, its dwell time running from now.
+    pub fn new() -> Self {
+        let rotation = settings::get().rotation;
+        Rotation {
+            slot: (0..ROTATION_SLOTS).find(|&slot| rotation.slots[slot].dwell_seconds > 0).unwrap_or(0),
+            since: Instant::now(),
+            paused_until: None,
+        }
+    }
+
+    /// Face of the current slot, none when the rotation is off.
+    pub fn face(&self) -> Option<Face> {
+        let rotation = settings::get().rotation;
+        let slot = rotation.slots[self.slot];
+        (rotation.enabled && slot.dwell_seconds > 0).then_some(slot.face)
+    }
+
+    /// Face switched by hand, it stays until the pause runs out.
+    pub fn pause(&mut self) {
+        self.paused_until = Some(Instant::now() + MANUAL_PAUSE);
+    }
+
+    /// Face to switch to, when the current one has been shown for its dwell time
+    /// or a manual pause has just ended.
+    pub fn next_face(&mut self) -> Option<Face> {
+        let rotation = settings::get().rotation;
+        if !rotation.enabled {
+            return None;
+        }
+
+        let now = Instant::now();
+        if let Some(until) = self.paused_until {
+            if now < until {
+                return None;
+            }
+            // Back to where the rotation was before the manual switch
+            self.paused_until = None;
+            self.since = now;
+            let slot = rotation.slots[self.slot];
+            return (slot.dwell_seconds > 0).then_some(slot.face);
+        }
+
+        let current = rotation.slots[self.slot];
+        if current.dwell_seconds > 0 && now - self.since < Duration::from_secs(current.dwell_seconds as u64) {
+            return None;
+        }
+
+        let next = (1..=ROTATION_SLOTS)
+            .map(|step| (self.slot + step) % ROTATION_SLOTS)
+            .find(|&slot| rotation.slots[slot].dwell_seconds > 0)?;
+        self.since = now;
+        if next == self.slot {
+            return None;
+        }
+
+        info! {"Rotating to slot {}", next};
+        self.slot = next;
+        Some(rotation.slots[next].face)
+    }
+}
@@ -0,0 +1,227 @@
+use defmt::info;
+use ds1307::{DateTimeAccess, Datelike, Ds1307, Error, NaiveDateTime, Timelike};
+use embassy_stm32::dma::NoDma;
+use embassy_stm32::gpio::{Level, OutputOpenDrain, Pull, Speed};
+use embassy_stm32::i2c::{self, I2c};
+use embassy_stm32::peripherals::{I2C1, PB6, PB7};
+use embassy_stm32::time::Hertz;
+use embassy_time::{block_for, Duration, Instant, Timer};
+
+use super::backup_rtc::BackupRtc;
+use super::diagnostics;
+
+pub type RtcError = Error<i2c::Error>;
+
+pub const I2C_FREQUENCY: Hertz = Hertz(100_000);
+
+const RECOVERY_RETRIES: u32 = 3;
+const RECOVERY_BACKOFF_MIN: Duration = Duration::from_secs(1);
+const RECOVERY_BACKOFF_MAX: Duration = Duration::from_secs(60);
+// Half of a 100 kHz SCL period
+const BIT_BANG_DELAY: Duration = Duration::from_micros(5);
+
+// Anything earlier means the clock lost its time, DS1307 resets to 2000-01-01
+const MIN_PLAUSIBLE_YEAR: i32 = 2024;
+
+/// DS1307 with the STM32 backup-domain RTC as a fallback.
+///
+/// While the DS1307 answers, the internal RTC is kept in step with it. When an I2C
+/// transfer fails the time is served from the internal RTC and the DS1307 is
+/// resynchronised from it as soon as it responds again.
+pub struct Rtc<'a> {
+    // Empty only while the bus is being recovered
+    ds1307: Option<Ds1307<I2c<'a, I2C1, NoDma, NoDma>>>,
+    backup: BackupRtc,
+    degraded: bool,
+    time_set: bool,
+    recovery_backoff: Duration,
+    next_recovery: Instant,
+}
+
+impl<'a> Rtc<'a> {
+    pub fn new(ds1307: Ds1307<I2c<'a, I2C1, NoDma, NoDma>>, backup: BackupRtc) -> Self {
+        Rtc {
+            ds1307: Some(ds1307),
+            backup,
+            degraded: false,
+            time_set: true,
+            recovery_backoff: RECOVERY_BACKOFF_MIN,
+            next_recovery: Instant::MIN,
+        }
+    }
+
+    /// Starts the DS1307 oscillator if it was halted and checks that the stored time
+    /// is plausible. When only the DS1307 lost its time it is restored from the
+    /// internal RTC. Returns false when the time has to be set by the user.
+    pub fn check_time(&mut self) -> bool {
+        let halted = matches!(self.device().running(), Ok(false));
+        if halted {
+            info! {"DS1307 oscillator was halted"};
+            if let Err(_) = self.device().set_running() {
+                info! {"DS1307 oscillator start failed"};
+            }
+        }
+
+        self.time_set = match self.device().datetime() {
+            Ok(datetime) if !halted && is_plausible(&datetime) => true,
+            Ok(_) => self.restore_ds1307(),
+            Err(_) => {
+                self.degraded = true;
+                self.backup.datetime().is_some_and(|datetime| is_plausible(&datetime))
+            }
+        };
+
+        if !self.time_set {
+            info! {"Time is not set"};
+        }
+        self.time_set
+    }
+
+    pub fn is_time_set(&self) -> bool {
+        self.time_set
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.degraded
+    }
+
+    pub fn datetime(&mut self) -> Result<NaiveDateTime, RtcError> {
+        match self.device().datetime() {
+            Ok(datetime) => {
+                if self.degraded {
+                    self.resync_ds1307()?;
+                    return self.device().datetime();
+                }
+
+                self.follow_ds1307(&datetime);
+                Ok(datetime)
+            }
+            Err(err) => {
+                if !self.degraded {
+                    info! {"DS1307 not responding, switching to internal RTC"};
+                    self.degraded = true;
+                }
+
+                self.backup.datetime().ok_or(err)
+            }
+        }
+    }
+
+    pub fn set_datetime(&mut self, datetime: &NaiveDateTime) -> Result<(), RtcError> {
+        self.backup.set_datetime(datetime);
+        self.time_set = true;
+
+        match self.device().set_datetime(datetime) {
+            Ok(()) => {
+                self.degraded = false;
+                Ok(())
+            }
+            // Internal RTC already holds the new time, DS1307 gets it on resync
+            Err(_) if self.backup.is_running() => {
+                self.degraded = true;
+                Ok(())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Frees a stuck bus and re-creates the I2C driver while the DS1307 keeps failing.
+    /// Rounds of retries are spaced with an exponential backoff.
+    pub async fn recover_bus(&mut self) {
+        if !self.degraded || Instant::now() < self.next_recovery {
+            return;
+        }
+
+        for attempt in 0..RECOVERY_RETRIES {
+            Timer::after_millis(10 << attempt).await;
+            self.reset_bus();
+
+            let recovered = self.device().datetime().is_ok();
+            diagnostics::record_i2c_recovery(recovered);
+            info! {"I2C recovery attempt {}: {}", attempt + 1, recovered};
+
+            if recovered {
+                self.recovery_backoff = RECOVERY_BACKOFF_MIN;
+                return;
+            }
+        }
+
+        self.next_recovery = Instant::now() + self.recovery_backoff;
+        self.recovery_backoff = (self.recovery_backoff * 2).min(RECOVERY_BACKOFF_MAX);
+    }
+
+    fn reset_bus(&mut self) {
+        // Old driver has to be dropped before the pins and peripheral are taken over again
+        drop(self.ds1307.take().map(Ds1307::destroy));
+
+        {
+            let mut scl = OutputOpenDrain::new(unsafe { PB6::steal() }, Level::High, Speed::Low, Pull::None);
+            let mut sda = OutputOpenDrain::new(unsafe { PB7::steal() }, Level::High, Speed::Low, Pull::None);
+
+            // Slave holding SDA low finishes its byte within nine clocks
+            for _ in 0..9 {
+                if sda.is_high() {
+                    break;
+                }
+                scl.set_low();
+                block_for(BIT_BANG_DELAY);
+                scl.set_high();
+                block_for(BIT_BANG_DELAY);
+            }
+
+            // STOP condition
+            sda.set_low();
+            block_for(BIT_BANG_DELAY);
+            scl.set_high();
+            block_for(BIT_BANG_DELAY);
+            sda.set_high();
+            block_for(BIT_BANG_DELAY);
+        }
+
+        let i2c = unsafe {
+            I2c::new(I2C1::steal(), PB6::steal(), PB7::steal(), crate::Irqs, NoDma, NoDma,
+                I2C_FREQUENCY, Default::default())
+        };
+        self.ds1307 = Some(Ds1307::new(i2c));
+    }
+
+    fn device(&mut self) -> &mut Ds1307<I2c<'a, I2C1, NoDma, NoDma>> {
+        self.ds1307.as_mut().expect("DS1307 driver is recreated in reset_bus")
+    }
+
+    fn resync_ds1307(&mut self) -> Result<(), RtcError> {
+        if let Some(datetime) = self.backup.datetime() {
+            self.device().set_datetime(&datetime)?;
+        }
+
+        info! {"DS1307 is back, resynchronised from internal RTC"};
+        self.degraded = false;
+        Ok(())
+    }
+
+    fn restore_ds1307(&mut self) -> bool {
+        match self.backup.datetime() {
+            Some(datetime) if is_plausible(&datetime) => {
+                info! {"DS1307 lost time, restored from internal RTC"};
+                self.device().set_datetime(&datetime).is_ok()
+            }
+            _ => false,
+        }
+    }
+
+    fn follow_ds1307(&mut self, datetime: &NaiveDateTime) {
+        let drift = match self.backup.datetime() {
+            Some(backup) => (*datetime - backup).num_seconds().abs(),
+            None => return,
+        };
+
+        // One second of slack, both clocks tick independently
+        if drift > 1 || datetime.second() == 0 && drift != 0 {
+            self.backup.set_datetime(datetime);
+        }
+    }
+}
+
+fn is_plausible(datetime: &NaiveDateTime) -> bool {
+    datetime.year() >= MIN_PLAUSIBLE_YEAR
+}
@@ -0,0 +1,112 @@
+use embassy_stm32::dma::NoDma;
+use embassy_stm32::i2c::{Error, I2c};
+use embassy_stm32::peripherals::I2C2;
+
+use crate::error::ClockError;
+use super::display_backend::DisplayBackend;
+use super::matrix_display::MatrixDisplay;
+
+const ADDRESS: u8 = 0x3C;
+const WIDTH: usize = 128;
+const PAGES: usize = 4;
+// Every matrix pixel becomes a 4x4 block, 32x8 pixels fill the 128x32 panel
+const SCALE: usize = 4;
+
+const COMMAND: u8 = 0x00;
+const DATA: u8 = 0x40;
+const CONTRAST: u8 = 0x81;
+const DISPLAY_OFF: u8 = 0xAE;
+const DISPLAY_ON: u8 = 0xAF;
+
+const INIT: &[u8] = &[
+    DISPLAY_OFF,
+    0xD5, 0x80, // clock divide
+    0xA8, 0x1F, // multiplex 32 rows
+    0xD3, 0x00, // display offset
+    0x40,       // start line 0
+    0x8D, 0x14, // charge pump on
+    0x20, 0x00, // horizontal addressing
+    0xA1,       // column 127 mapped to SEG0
+    0xC8,       // scan from COM31 to COM0
+    0xDA, 0x02, // sequential COM pins
+    0xD9, 0xF1, // pre-charge period
+    0xDB, 0x40, // VCOMH level
+    0xA4,       // show RAM content
+    0xA6,       // not inverted
+    0x2E,       // no scrolling
+];
+
+/// 128x32 SSD1306 OLED on I2C2 (PB10 SCL, PB11 SDA), the matrix frame scaled 4 times.
+pub struct Ssd1306<'a> {
+    i2c: I2c<'a, I2C2, NoDma, NoDma>,
+    shadow: Option<[[u8; 8]; 4]>,
+}
+
+impl<'a> Ssd1306<'a> {
+    pub fn new(i2c: I2c<'a, I2C2, NoDma, NoDma>) -> Result<Self, Error> {
+        let mut display = Ssd1306 { i2c, shadow: None };
+
+        for &command in INIT {
+            display.command(command)?;
+        }
+
+        Ok(display)
+    }
+
+    fn command(&mut self, command: u8) -> Result<(), Error> {
+        self.i2c.blocking_write(ADDRESS, &[COMMAND, command])
+    }
+
+    fn set_window(&mut self) -> Result<(), Error> {
+        for command in [0x21, 0x00, WIDTH as u8 - 1, 0x22, 0x00, PAGES as u8 - 1] {
+            self.command(command)?;
+        }
+        Ok(())
+    }
+}
+
+impl DisplayBackend for Ssd1306<'_> {
+    async fn write_frame(&mut self, matrices: &MatrixDisplay) -> Result<(), ClockError> {
+        let frame = matrices.frame();
+        if self.shadow == Some(frame) {
+            return Ok(());
+        }
+
+        self.set_window()?;
+
+        // One page is 8 panel rows, that is two matrix rows
+        for page in 0..PAGES {
+            let mut buffer = [0; WIDTH + 1];
+            buffer[0] = DATA;
+
+            for column in 0..WIDTH {
+                let x = column / SCALE;
+                let bits = frame[x / 8];
+
+                for half in 0..2 {
+                    if bits[page * 2 + half] & (0x80 >> (x % 8)) != 0 {
+                        buffer[1 + column] |= 0x0F << (half * SCALE);
+                    }
+                }
+            }
+
+            self.i2c.blocking_write(ADDRESS, &buffer)?;
+        }
+
+        self.shadow = Some(frame);
+        Ok(())
+    }
+
+    async fn set_intensity(&mut self, intensity: u8) -> Result<(), ClockError> {
+        self.command(CONTRAST)?;
+        Ok(self.command((intensity & 0x0F) * 16 + 15)?)
+    }
+
+    async fn power_on(&mut self) -> Result<(), ClockError> {
+        Ok(self.command(DISPLAY_ON)?)
+    }
+
+    async fn power_off(&mut self) -> Result<(), ClockError> {
+        Ok(self.command(DISPLAY_OFF)?)
+    }
+}
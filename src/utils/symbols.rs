@@ -6,6 +6,25 @@ pub const BLANK: [u8; 8] = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
 #[allow(dead_code)]
 pub const DOT: [u8; 8] = [0x00,0x00,0x00,0x00,0x00,0x70,0x70,0x70];
 pub const EXCLAMETION_MARK: [u8; 8] = [0x18,0x18,0x18,0x18,0x00,0x18,0x18,0x00];
+pub const COLON: [u8; 8] = [0x00,0x18,0x18,0x00,0x00,0x18,0x18,0x00];
+pub const MINUS: [u8; 8] = [0x00,0x00,0x00,0x7e,0x00,0x00,0x00,0x00];
+pub const PERIOD: [u8; 8] = [0x00,0x00,0x00,0x00,0x00,0x18,0x18,0x00];
+pub const SLASH: [u8; 8] = [0x03,0x06,0x0c,0x18,0x30,0x60,0x40,0x00];
+pub const APOSTROPHE: [u8; 8] = [0x18,0x18,0x30,0x00,0x00,0x00,0x00,0x00];
+pub const DEGREE: [u8; 8] = [0x38,0x6c,0x38,0x00,0x00,0x00,0x00,0x00];
+
+// Polish letters, accents take the top row so the letter is one row shorter
+const POLISH_LETTERS: [(char, [u8; 8]); 9] = [
+    ('Ą', [0x18, 0x3c, 0x66, 0x66, 0x7e, 0x66, 0x66, 0x03]),
+    ('Ć', [0x0c, 0x1e, 0x33, 0x60, 0x60, 0x33, 0x1e, 0x00]),
+    ('Ę', [0x7f, 0x31, 0x34, 0x3c, 0x34, 0x31, 0x7f, 0x06]),
+    ('Ł', [0x78, 0x30, 0x34, 0x38, 0x71, 0x33, 0x7f, 0x00]),
+    ('Ń', [0x0c, 0x63, 0x73, 0x7b, 0x6f, 0x67, 0x63, 0x00]),
+    ('Ó', [0x0c, 0x1c, 0x36, 0x63, 0x63, 0x36, 0x1c, 0x00]),
+    ('Ś', [0x0c, 0x3c, 0x66, 0x38, 0x0e, 0x66, 0x3c, 0x00]),
+    ('Ź', [0x0c, 0x7f, 0x46, 0x0c, 0x18, 0x31, 0x7f, 0x00]),
+    ('Ż', [0x18, 0x7f, 0x46, 0x0c, 0x18, 0x31, 0x7f, 0x00]),
+];
 
 pub const DIGITS: [[u8; 8]; 10] = [
     [0x78, 0xcc, 0x9c, 0xb4, 0xe4, 0xcc, 0x78, 0x00],  // (zero)
@@ -83,6 +102,33 @@ impl Letters {
     }
 }
 
+pub const LETTERS: [Letters; 26] = [
+    Letters::A, Letters::B, Letters::C, Letters::D, Letters::E, Letters::F, Letters::G,
+    Letters::H, Letters::I, Letters::J, Letters::K, Letters::L, Letters::M, Letters::N,
+    Letters::O, Letters::P, Letters::Q, Letters::R, Letters::S, Letters::T, Letters::U,
+    Letters::V, Letters::W, Letters::X, Letters::Y, Letters::Z,
+];
+
+/// Bitmap for a single character, unknown characters are blank.
+pub fn glyph(c: char) -> [u8; 8] {
+    match c {
+        'A'..='Z' => LETTERS[c as usize - 'A' as usize].bytes(),
+        'a'..='z' => LETTERS[c as usize - 'a' as usize].bytes(),
+        '0'..='9' => DIGITS[c as usize - '0' as usize],
+        ':' => COLON,
+        '!' => EXCLAMETION_MARK,
+        '-' => MINUS,
+        '.' => PERIOD,
+        '/' => SLASH,
+        '\'' => APOSTROPHE,
+        '°' => DEGREE,
+        _ => {
+            let upper = c.to_uppercase().next().unwrap_or(c);
+            POLISH_LETTERS.iter().find(|(letter, _)| *letter == upper).map_or(BLANK, |(_, bytes)| *bytes)
+        }
+    }
+}
+
 pub const FREQUENCIES: [(&str, Hertz); 25] = [
     ("C3", hz(131)),
     ("C#3", hz(139)),
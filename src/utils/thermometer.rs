@@ -0,0 +1,66 @@
+use core::convert::Infallible;
+use core::sync::atomic::{AtomicI16, Ordering};
+
+use defmt::warn;
+use ds18b20::{Ds18b20, Resolution};
+use embassy_stm32::gpio::{Level, OutputOpenDrain, Pull, Speed};
+use embassy_stm32::peripherals::PB12;
+use embassy_time::{Delay, Timer};
+use one_wire_bus::OneWire;
+
+use crate::error::ClockError;
+
+const SAMPLE_PERIOD_MS: u64 = 5000;
+// Kept until the first good reading and after every failed one
+const NO_READING: i16 = i16::MIN;
+
+static READING: AtomicI16 = AtomicI16::new(NO_READING);
+
+type Bus = OneWire<OutputOpenDrain<'static, PB12>>;
+
+/// Last temperature in tenths of a degree Celsius, `Sensor` when the DS18B20 did
+/// not answer.
+pub fn temperature() -> Result<i16, ClockError> {
+    match READING.load(Ordering::Relaxed) {
+        NO_READING => Err(ClockError::Sensor),
+        tenths => Ok(tenths),
+    }
+}
+
+/// Reads the DS18B20 on PB12, a one-wire bus with an external 4.7k pull-up.
+#[embassy_executor::task]
+pub async fn measure(pin: PB12) {
+    let Ok(mut bus) = OneWire::new(OutputOpenDrain::new(pin, Level::High, Speed::Low, Pull::None)) else {
+        warn! {"One-wire bus held low, no temperature"};
+        return;
+    };
+
+    loop {
+        match read(&mut bus).await {
+            Ok(tenths) => READING.store(tenths, Ordering::Relaxed),
+            Err(err) => {
+                READING.store(NO_READING, Ordering::Relaxed);
+                warn! {"{}: {}", err.code(), err};
+            }
+        }
+        Timer::after_millis(SAMPLE_PERIOD_MS).await;
+    }
+}
+
+// The first DS18B20 on the bus, searched for every time so a sensor plugged in
+// later is found too
+async fn read(bus: &mut Bus) -> Result<i16, ClockError> {
+    let sensor = match bus.device_search(None, false, &mut Delay) {
+        Ok(Some((address, _))) if address.family_code() == ds18b20::FAMILY_CODE => {
+            Ds18b20::new::<Infallible>(address).map_err(|_| ClockError::Sensor)?
+        }
+        _ => return Err(ClockError::Sensor),
+    };
+
+    sensor.start_temp_measurement(bus, &mut Delay).map_err(|_| ClockError::Sensor)?;
+    // Conversion runs in the sensor, other tasks keep going meanwhile
+    Timer::after_millis(Resolution::Bits12.max_measurement_time_millis() as u64).await;
+    let data = sensor.read_data(bus, &mut Delay).map_err(|_| ClockError::Sensor)?;
+
+    Ok((data.temperature * 10.0) as i16)
+}
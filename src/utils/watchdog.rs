@@ -0,0 +1,92 @@
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use defmt::{error, info};
+use embassy_stm32::peripherals::IWDG;
+use embassy_stm32::wdg::IndependentWatchdog;
+use embassy_time::{Instant, Timer};
+
+// Hardware reset fires when nothing fed the IWDG for this long
+pub const WATCHDOG_TIMEOUT_US: u32 = 8_000_000;
+const CHECK_PERIOD_MS: u64 = 500;
+
+#[derive(Clone, Copy, defmt::Format)]
+pub enum Activity {
+    Display,
+    RtcTick,
+    Input,
+}
+
+const ACTIVITIES: [Activity; 3] = [Activity::Display, Activity::RtcTick, Activity::Input];
+
+impl Activity {
+    fn index(&self) -> usize {
+        match self {
+            Activity::Display => 0,
+            Activity::RtcTick => 1,
+            Activity::Input => 2,
+        }
+    }
+
+    // Longest time between two reports before the activity counts as stuck,
+    // menus show info screens for up to two seconds without polling buttons
+    fn deadline_ms(&self) -> u32 {
+        match self {
+            Activity::Display => 3_000,
+            Activity::RtcTick => 3_000,
+            Activity::Input => 4_000,
+        }
+    }
+}
+
+static LAST_PROGRESS: [AtomicU32; 3] = [AtomicU32::new(0), AtomicU32::new(0), AtomicU32::new(0)];
+// RTC is only ticked in clock mode, it is resumed there
+static PAUSED: [AtomicBool; 3] = [AtomicBool::new(false), AtomicBool::new(true), AtomicBool::new(false)];
+
+fn now_ms() -> u32 {
+    Instant::now().as_millis() as u32
+}
+
+pub fn report(activity: Activity) {
+    LAST_PROGRESS[activity.index()].store(now_ms(), Ordering::Relaxed);
+}
+
+/// Excludes an activity from supervision, e.g. RTC ticks while the menu is open.
+pub fn pause(activity: Activity) {
+    PAUSED[activity.index()].store(true, Ordering::Relaxed);
+}
+
+pub fn resume(activity: Activity) {
+    report(activity);
+    PAUSED[activity.index()].store(false, Ordering::Relaxed);
+}
+
+fn stalled_activity() -> Option<Activity> {
+    let now = now_ms();
+
+    ACTIVITIES.into_iter().find(|activity| {
+        let paused = PAUSED[activity.index()].load(Ordering::Relaxed);
+        let last = LAST_PROGRESS[activity.index()].load(Ordering::Relaxed);
+
+        !paused && now.wrapping_sub(last) > activity.deadline_ms()
+    })
+}
+
+/// Feeds the IWDG only while every supervised activity keeps reporting progress.
+#[embassy_executor::task]
+pub async fn supervise(mut wdg: IndependentWatchdog<'static, IWDG>) {
+    for activity in ACTIVITIES {
+        report(activity);
+    }
+
+    wdg.unleash();
+    info! {"Watchdog running"};
+
+    loop {
+        match stalled_activity() {
+            None => wdg.pet(),
+            Some(activity) => error! {"{} stalled, watchdog not fed", activity},
+        }
+
+        Timer::after_millis(CHECK_PERIOD_MS).await;
+    }
+}